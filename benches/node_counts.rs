@@ -0,0 +1,119 @@
+//! Compares the number of positions visited by each search algorithm on fixed Connect Four
+//! positions. Run with `cargo bench --bench node_counts`.
+
+use std::num::{NonZeroU8, NonZeroUsize};
+
+use min_max::game::evaluator::GameScore;
+use min_max::min_max::cancellation::CancellationToken;
+use min_max::min_max::config::{PlayOrder, SearchConfig};
+use min_max::min_max::make_unmake;
+use min_max::min_max::transposition::TranspositionTable;
+use min_max::min_max::Algorithm;
+use min_max::{ConnectFour, Game, GameNode, Player, SearchStats};
+
+/// Columns played from the start
+const POSITIONS: [&str; 6] = ["", "4", "44", "4435", "3445", "43354421"];
+
+const DEPTH: u32 = 9;
+
+const ALGORITHMS: [Algorithm; 2] = [Algorithm::AlphaBeta, Algorithm::PrincipalVariation];
+
+fn main() {
+    // the counts only depend on the order of the plays with a single thread and a fixed seed
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build_global()
+        .unwrap();
+
+    println!("Positions visited {DEPTH} plays ahead:");
+    println!(
+        "{:<10} {:>10} {:>10} {:>8} {:>10} {:>10} {:>8}",
+        "position", "tree a-b", "tree pvs", "pvs/a-b", "m/u a-b", "m/u pvs", "pvs/a-b"
+    );
+    let mut totals = [0; 4];
+    for columns in POSITIONS {
+        let (game, player) = play_columns(columns);
+        let mut counts = Vec::new();
+        for algorithm in ALGORITHMS {
+            let stats = tree_search(&game, player, columns.len() as u32, &config(algorithm));
+            counts.push(stats.nodes);
+        }
+        for algorithm in ALGORITHMS {
+            let stats = make_unmake_search(&game, player, &config(algorithm));
+            counts.push(stats.nodes);
+        }
+        for (total, count) in totals.iter_mut().zip(&counts) {
+            *total += count;
+        }
+        print_row(columns, &counts);
+    }
+    print_row("total", &totals);
+}
+
+fn config(algorithm: Algorithm) -> SearchConfig<i32> {
+    SearchConfig::default()
+        .with_algorithm(algorithm)
+        .with_play_order(PlayOrder::Seeded(0))
+}
+
+fn print_row(name: &str, counts: &[u64]) {
+    let ratio = |pvs: u64, alpha_beta: u64| pvs as f64 / alpha_beta as f64;
+    println!(
+        "{:<10} {:>10} {:>10} {:>8.2} {:>10} {:>10} {:>8.2}",
+        if name.is_empty() { "(start)" } else { name },
+        counts[0],
+        counts[1],
+        ratio(counts[1], counts[0]),
+        counts[2],
+        counts[3],
+        ratio(counts[3], counts[2]),
+    );
+}
+
+fn play_columns(columns: &str) -> (ConnectFour, NonZeroU8) {
+    let mut game = ConnectFour::new();
+    let mut player = NonZeroU8::new(1).unwrap();
+    for column in columns.chars() {
+        let column = column.to_digit(10).unwrap() as usize;
+        game.play(player, NonZeroUsize::new(column).unwrap())
+            .unwrap();
+        player = player.other();
+    }
+    (game, player)
+}
+
+fn tree_search(
+    game: &ConnectFour,
+    player: NonZeroU8,
+    depth: u32,
+    config: &SearchConfig<i32>,
+) -> SearchStats {
+    let mut game_tree = GameNode::new_root(game.clone(), player, depth);
+    game_tree.explore_children(
+        player,
+        DEPTH,
+        config,
+        &GameScore,
+        &TranspositionTable::default(),
+        None,
+        &CancellationToken::new(),
+    )
+}
+
+fn make_unmake_search(
+    game: &ConnectFour,
+    player: NonZeroU8,
+    config: &SearchConfig<i32>,
+) -> SearchStats {
+    make_unmake::best_play(
+        game,
+        player,
+        DEPTH,
+        config,
+        &GameScore,
+        &TranspositionTable::default(),
+        None,
+        &CancellationToken::new(),
+    )
+    .1
+}
@@ -0,0 +1,108 @@
+//! Measures how much faster the searches are with more threads, on fixed Connect Four positions.
+//! Run with `cargo bench --bench parallel_speedup`, optionally followed by `-- <max threads>`
+//! (all the cores by default).
+
+use std::num::{NonZeroU8, NonZeroUsize};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rayon::ThreadPoolBuilder;
+
+use min_max::game::evaluator::GameScore;
+use min_max::min_max::cancellation::CancellationToken;
+use min_max::min_max::config::SearchConfig;
+use min_max::min_max::make_unmake;
+use min_max::min_max::transposition::TranspositionTable;
+use min_max::{ConnectFour, Game, GameNode, Player};
+
+/// Columns played from the start
+const POSITIONS: [&str; 3] = ["", "4435", "43354421"];
+
+const DEPTH: u32 = 11;
+
+fn main() {
+    let max_threads = std::env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with('-'))
+        .map(|arg| {
+            arg.parse()
+                .expect("the argument should be a number of threads")
+        })
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get));
+
+    println!("Time to explore {DEPTH} plays ahead of each position, in ms:");
+    println!(
+        "{:<8} {:>10} {:>8} {:>10} {:>8}",
+        "threads", "tree", "speedup", "m/u", "speedup"
+    );
+    let mut sequential = None;
+    for threads in 1..=max_threads {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let times = thread_pool.install(|| {
+            let mut times = [Duration::ZERO; 2];
+            for columns in POSITIONS {
+                let (game, player) = play_columns(columns);
+                times[0] += tree_search(&game, player, columns.len() as u32);
+                times[1] += make_unmake_search(&game, player);
+            }
+            times
+        });
+        let sequential = *sequential.get_or_insert(times);
+        let speedup = |index: usize| sequential[index].as_secs_f64() / times[index].as_secs_f64();
+        println!(
+            "{:<8} {:>10} {:>8.2} {:>10} {:>8.2}",
+            threads,
+            times[0].as_millis(),
+            speedup(0),
+            times[1].as_millis(),
+            speedup(1),
+        );
+    }
+}
+
+fn play_columns(columns: &str) -> (ConnectFour, NonZeroU8) {
+    let mut game = ConnectFour::new();
+    let mut player = NonZeroU8::new(1).unwrap();
+    for column in columns.chars() {
+        let column = column.to_digit(10).unwrap() as usize;
+        game.play(player, NonZeroUsize::new(column).unwrap())
+            .unwrap();
+        player = player.other();
+    }
+    (game, player)
+}
+
+fn tree_search(game: &ConnectFour, player: NonZeroU8, depth: u32) -> Duration {
+    let table = TranspositionTable::default();
+    let start = Instant::now();
+    let mut game_tree = GameNode::new_root(game.clone(), player, depth);
+    game_tree.explore_children(
+        player,
+        DEPTH,
+        &SearchConfig::default(),
+        &GameScore,
+        &table,
+        None,
+        &CancellationToken::new(),
+    );
+    start.elapsed()
+}
+
+fn make_unmake_search(game: &ConnectFour, player: NonZeroU8) -> Duration {
+    let table = TranspositionTable::default();
+    let start = Instant::now();
+    make_unmake::best_play(
+        game,
+        player,
+        DEPTH,
+        &SearchConfig::default(),
+        &GameScore,
+        &table,
+        None,
+        &CancellationToken::new(),
+    );
+    start.elapsed()
+}
@@ -1,28 +1,136 @@
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::game::evaluator::{Evaluator, GameScore};
 use crate::game::player::Player;
 use crate::game::Game;
+use crate::min_max::analysis::AnalyzedPlay;
+use crate::min_max::cancellation::CancellationToken;
+use crate::min_max::config::SearchConfig;
+use crate::min_max::make_unmake;
 use crate::min_max::node::GameNode;
+use crate::min_max::outcome::{Outcome, Solution};
+use crate::min_max::stats::SearchStats;
+use crate::min_max::transposition::TranspositionTable;
+use crate::min_max::Variation;
 use crate::scalar::Scalar;
 
-pub struct Bot<G: Game> {
+mod tests;
+
+/// How long the bot may think before playing
+#[derive(Debug, Copy, Clone)]
+pub enum SearchLimit {
+    /// Deepens iteratively up to the given number of plays: explores 1, 2, 3... plays ahead, each
+    /// exploration starting with the best plays of the previous one
+    Depth(u32),
+    /// Explores 1, 2, 3... plays ahead until the time is over, and plays the best play of the
+    /// deepest exploration that could be completed
+    MoveTime(Duration),
+    /// Like `MoveTime`, but the time of each play is taken from a clock for the whole game, which
+    /// gets `increment` more time after each play
+    Clock {
+        remaining: Duration,
+        increment: Duration,
+    },
+}
+
+pub struct Bot<G: Game, E: Evaluator<G> = GameScore> {
     player: G::Player,
     game_tree: Option<GameNode<G>>,
-    max_depth: u32,
+    limit: SearchLimit,
+    evaluator: E,
     times: Vec<u64>,
-    play_count: u32,
+    /// The stats of the searches of each play, see `search_stats`
+    stats: Vec<Vec<SearchStats>>,
+    /// The line expected by the last play, see `principal_variation`
+    principal_variation: Variation<G>,
+    /// The result of the game proven by the last play, see `solution`
+    solution: Option<Solution>,
+    table: TranspositionTable<G>,
+    cancellation: CancellationToken,
+    /// Whether to search with `make_unmake::best_play` instead of keeping a tree of games
+    make_unmake: bool,
+    config: SearchConfig<G::Score>,
+    /// The threads of the searches, or None to use the global rayon pool
+    thread_pool: Option<ThreadPool>,
 }
 
-impl<G: Game> Bot<G> {
+impl<G: Game, E: Evaluator<G> + Default> Bot<G, E> {
     pub fn new(player: G::Player, max_depth: u32) -> Self {
+        Self::with_limit(player, SearchLimit::Depth(max_depth))
+    }
+
+    pub fn with_limit(player: G::Player, limit: SearchLimit) -> Self {
+        Self::with_evaluator(player, limit, E::default())
+    }
+}
+
+impl<G: Game, E: Evaluator<G>> Bot<G, E> {
+    /// The deepest exploration tried with a time limit
+    const MAX_ITERATIVE_DEPTH: u32 = 64;
+
+    /// With a clock, each play uses 1/CLOCK_PLAYS of the remaining time (plus the increment)
+    const CLOCK_PLAYS: u32 = 20;
+
+    /// Creates a bot scoring the positions with `evaluator` when it stops exploring
+    pub fn with_evaluator(player: G::Player, limit: SearchLimit, evaluator: E) -> Self {
         Self {
             player,
-            /// game_tree should never be None
+            // game_tree should never be None
             game_tree: Some(GameNode::new_root(G::new(), player, 0)),
-            max_depth,
+            limit,
+            evaluator,
             times: Vec::new(),
-            play_count: 0,
+            stats: Vec::new(),
+            principal_variation: Vec::new(),
+            solution: None,
+            table: TranspositionTable::default(),
+            cancellation: CancellationToken::new(),
+            make_unmake: false,
+            config: SearchConfig::default(),
+            thread_pool: None,
         }
     }
 
+    /// Limits the memory used to remember already explored positions
+    pub fn with_table_memory(mut self, max_memory: usize) -> Self {
+        self.table = TranspositionTable::new(max_memory);
+        self
+    }
+
+    /// Searches by playing and undoing plays on a single game instead of keeping a tree of all the
+    /// explored games, see `make_unmake::best_play`
+    pub fn with_make_unmake(mut self) -> Self {
+        self.make_unmake = true;
+        self
+    }
+
+    /// Searches as configured by `config` instead of the default configuration
+    pub fn with_config(mut self, config: SearchConfig<G::Score>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Searches with `threads` threads of its own instead of the global rayon pool, which uses all
+    /// the cores by default. With a single thread, the search is sequential.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("Could not create the threads of the bot");
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    /// A token that can be used from another thread to stop the current call to `play`, which
+    /// will then play the best play found so far, or to `analyze`. Each call resets the token
+    /// when it starts, so a cancellation made between two calls is ignored.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancellation.clone()
+    }
+
     pub fn other_played(&mut self, play: G::InputCoordinate) -> Result<(), &str> {
         let had_children = self
             .game_tree
@@ -46,51 +154,354 @@ impl<G: Game> Bot<G> {
                 return Err(err);
             }
             if had_children {
-                println!("Unexpected move... Maybe you are a pure genius, or a pure idiot.");
+                log::info!("Unexpected move... Maybe you are a pure genius, or a pure idiot.");
             }
             let depth = new_game_tree.depth() + 1;
             let game = new_game_tree.into_game();
             self.game_tree = Some(GameNode::new_root(game, self.player, depth));
         }
-        self.play_count += 1;
         Ok(())
     }
 
+    /// Chooses and makes the play of the bot. The game must not be over.
     pub fn play(&mut self) -> G::InputCoordinate {
-        let start = std::time::Instant::now();
+        let game = self.expect_game();
+        assert!(
+            game.get_winner().is_none() && !game.is_full(),
+            "the game is over"
+        );
+        let start = Instant::now();
+        // a cancellation only applies to the search it interrupts
+        self.cancellation.reset();
+        let game = self.expect_game().clone();
+        self.stats.push(Vec::new());
+        let remaining_plays = game.remaining_plays();
+        // close to the end, the exploration goes as far as the end instead of the depth limit
+        let endgame = remaining_plays.filter(|&plays| plays <= self.config.endgame_plays);
+        if let Some(plays) = endgame {
+            log::info!("At most {plays} plays left, exploring until the end of the game...");
+        }
+        let best = match self.limit {
+            SearchLimit::Depth(max_depth) => {
+                let depth = endgame.map_or(max_depth, |plays| plays.min(max_depth));
+                let best = self.iterative_deepening(start, 1..=depth, None);
+                match endgame {
+                    Some(plays) if plays > depth => self.explore_endgame(best, plays),
+                    _ => best,
+                }
+            }
+            SearchLimit::MoveTime(move_time) => self.iterative_deepening(
+                start,
+                1..=endgame.unwrap_or(Self::MAX_ITERATIVE_DEPTH),
+                Some(move_time),
+            ),
+            SearchLimit::Clock {
+                remaining,
+                increment,
+            } => {
+                let move_time = (remaining / Self::CLOCK_PLAYS + increment).min(remaining / 2);
+                self.iterative_deepening(
+                    start,
+                    1..=endgame.unwrap_or(Self::MAX_ITERATIVE_DEPTH),
+                    Some(move_time),
+                )
+            }
+        };
+        let (play, weight, depth) = self.choose_play(best);
+        // the wins and losses are always forced, but the other weights only mean a draw if every
+        // line was explored until the end of the game
+        let explored_to_end = remaining_plays.is_some_and(|plays| depth >= plays);
+        self.solution = match Outcome::decode(weight) {
+            Outcome::Heuristic(_) if !explored_to_end => None,
+            outcome => Some(outcome.solution()),
+        };
+
+        // println!("Tree:\n {}", game_tree.debug(2));
+        log::info!("Comparing possibilities...");
+        let game_tree = self.game_tree.take().unwrap();
+        let (is_known_move, mut game_tree) = game_tree.try_into_child(play);
+        if !is_known_move {
+            // only happens without a tree, or if the search was stopped before the end of the
+            // first exploration
+            game_tree
+                .expect_game_mut()
+                .play(self.player, play)
+                .expect("play should be possible");
+            let depth = game_tree.depth() + 1;
+            game_tree = GameNode::new_root(game_tree.into_game(), self.player.other(), depth);
+        }
+        self.game_tree = Some(game_tree);
+
+        let elapsed = start.elapsed();
+        if let SearchLimit::Clock {
+            remaining,
+            increment,
+        } = &mut self.limit
+        {
+            *remaining = remaining.saturating_sub(elapsed) + *increment;
+        }
+        let time = elapsed.as_millis() as u64;
+        self.times.push(time);
+        let researches = self
+            .stats
+            .last()
+            .map_or(0, |stats| aspiration_researches(stats));
+        log::info!("Done in {time}ms, with {researches} aspiration re-searches");
+
+        // a draw may weigh as much as a loss or a win, depending on the contempt of the bot
+        if self.solution == Some(Solution::Draw) {
+            log::info!("It's a draw if nobody makes a mistake.");
+        } else if self.expects_draw(game) {
+            log::info!("Looks like a draw.");
+        } else {
+            match Outcome::decode(weight) {
+                Outcome::Win(plies) => log::info!("You're dead in {plies} plays, sorry."),
+                Outcome::Loss(plies) => log::info!("Ok I'm basically dead in {plies} plays..."),
+                Outcome::Heuristic(_) => {}
+            }
+        }
+
+        play
+    }
+
+    /// Scores every possible play of the bot, which must be the next one to play, by exploring
+    /// `max_depth` plays ahead, without playing. Returns the plays from the best to the worst, see
+    /// `GameNode::analyze`, or None if the analysis was cancelled.
+    pub fn analyze(&mut self, max_depth: u32) -> Option<Vec<AnalyzedPlay<G>>> {
+        // a cancellation only applies to the search it interrupts
+        self.cancellation.reset();
+        let game_tree = self
+            .game_tree
+            .as_mut()
+            .expect("Bot has not been initialized");
+        let (analysis, _) = install(self.thread_pool.as_ref(), || {
+            if self.make_unmake {
+                make_unmake::analyze(
+                    game_tree.game(),
+                    self.player,
+                    max_depth,
+                    &self.config,
+                    &self.evaluator,
+                    &self.table,
+                    None,
+                    &self.cancellation,
+                )
+            } else {
+                game_tree.analyze(
+                    self.player,
+                    max_depth,
+                    &self.config,
+                    &self.evaluator,
+                    &self.table,
+                    None,
+                    &self.cancellation,
+                )
+            }
+        });
+        analysis
+    }
+
+    /// Explores `max_depth` plays ahead and returns the principal variation, starting with the
+    /// best play, or None if the deadline was reached before the end
+    fn search(&mut self, max_depth: u32, deadline: Option<Instant>) -> Option<Variation<G>> {
         let game_tree = self
             .game_tree
             .as_mut()
             .expect("Bot has not been initialized");
-        game_tree.explore_children(self.player, self.max_depth, self.play_count);
+        let (result, stats) = install(self.thread_pool.as_ref(), || {
+            if self.make_unmake {
+                make_unmake::best_play(
+                    game_tree.game(),
+                    self.player,
+                    max_depth,
+                    &self.config,
+                    &self.evaluator,
+                    &self.table,
+                    deadline,
+                    &self.cancellation,
+                )
+            } else {
+                let stats = game_tree.explore_children(
+                    self.player,
+                    max_depth,
+                    &self.config,
+                    &self.evaluator,
+                    &self.table,
+                    deadline,
+                    &self.cancellation,
+                );
+                let result = Some(game_tree.principal_variation(max_depth))
+                    .filter(|variation| stats.completed && !variation.is_empty());
+                (result, stats)
+            }
+        });
+        if let Some(play_stats) = self.stats.last_mut() {
+            play_stats.push(stats);
+        }
+        result
+    }
 
-        // println!("Tree:\n {}", game_tree.debug(2));
-        println!("Comparing possibilities...");
-        self.game_tree = Some(self.game_tree.take().unwrap().into_best_child());
+    /// Explores deeper and deeper through `depths` until the last one is reached, `move_time` is
+    /// over or the search is cancelled. Each exploration starts with the best plays of the
+    /// previous one, so the deeper ones are faster. Returns the depth of the deepest completed
+    /// exploration with its principal variation, if any.
+    fn iterative_deepening(
+        &mut self,
+        start: Instant,
+        depths: RangeInclusive<u32>,
+        move_time: Option<Duration>,
+    ) -> Option<(u32, Variation<G>)> {
+        let deadline = move_time.map(|move_time| start + move_time);
+        let mut best = None;
+        for depth in depths {
+            match self.search(depth, deadline) {
+                Some(variation) => best = Some((depth, variation)),
+                None => break,
+            }
+            // the next exploration would probably take longer than all the previous ones
+            let no_time_left = move_time.is_some_and(|move_time| start.elapsed() * 2 > move_time);
+            if best
+                .as_ref()
+                .is_some_and(|(_, variation)| is_decided(variation))
+                || no_time_left
+            {
+                break;
+            }
+        }
+        best
+    }
+
+    /// Explores deeper than `best`, the deepest exploration of the depth limit, until the end of
+    /// the game `plays` plays ahead. As nothing else bounds it, this exploration gets at most
+    /// `SearchConfig::endgame_time`, and `best` is kept unless it reaches the end of the game.
+    fn explore_endgame(
+        &mut self,
+        best: Option<(u32, Variation<G>)>,
+        plays: u32,
+    ) -> Option<(u32, Variation<G>)> {
+        let (depth, variation) = best.as_ref()?;
+        if is_decided(variation) {
+            return best;
+        }
+        let endgame_time = self.config.endgame_time;
+        let deeper =
+            self.iterative_deepening(Instant::now(), depth + 1..=plays, Some(endgame_time));
+        match deeper {
+            Some((depth, variation)) if depth == plays || is_decided(&variation) => {
+                Some((depth, variation))
+            }
+            _ => {
+                log::info!("The end of the game was not reached in {endgame_time:?}");
+                best
+            }
+        }
+    }
 
-        self.play_count += 1;
+    /// The best play of the deepest exploration from `iterative_deepening` with its weight and
+    /// depth, which becomes the principal variation, or the first possible play if there is none
+    fn choose_play(
+        &mut self,
+        best: Option<(u32, Variation<G>)>,
+    ) -> (G::InputCoordinate, G::Score, u32) {
+        match best {
+            Some((depth, variation)) => {
+                log::info!("Explored {depth} plays ahead");
+                let (play, weight) = variation[0];
+                self.principal_variation = variation;
+                (play, weight, depth)
+            }
+            None => {
+                // no time for a single exploration
+                let play = self
+                    .expect_game()
+                    .possible_plays()
+                    .into_iter()
+                    .next()
+                    .expect("the game is not over");
+                self.principal_variation = vec![(play, G::Score::ZERO())];
+                (play, G::Score::ZERO(), 0)
+            }
+        }
+    }
 
-        let game_tree = self.game_tree.as_ref().unwrap();
+    /// The line of plays expected by the deepest search of the last play: the play of the bot,
+    /// then the expected answer, and so on, each with the weight of the position it leads to. It
+    /// may be shorter than the search depth, see `GameNode::principal_variation`.
+    pub fn principal_variation(&self) -> &[(G::InputCoordinate, G::Score)] {
+        &self.principal_variation
+    }
 
-        let time = start.elapsed().as_millis() as u64;
-        self.times.push(time);
-        println!("Done in {}ms", time);
+    /// The exact result of the game for the bot if the search of its last play proved it: a
+    /// forced win or loss (see `Outcome`), or a draw if every line was explored until the end of
+    /// the game (see `SearchConfig::with_endgame_plays`). The distances count the plays from the
+    /// position before the last play of the bot.
+    pub fn solution(&self) -> Option<Solution> {
+        self.solution
+    }
 
-        let weight_opt = game_tree.weight();
-        if weight_opt.is_some_and(|it| it > G::Score::MAX().add_towards_0(1000)) {
-            println!("You're dead, sorry.");
-        } else if weight_opt.is_some_and(|it| it < G::Score::MIN().add_towards_0(1000)) {
-            println!("Ok I'm basically dead...");
+    /// Whether the principal variation of the last play, played from `game`, ends with a draw
+    fn expects_draw(&self, mut game: G) -> bool {
+        let mut player = self.player;
+        for (play, _) in &self.principal_variation {
+            if game.play(player, *play).is_err() {
+                return false;
+            }
+            player = player.other();
         }
+        game.get_winner().is_none() && game.is_full()
+    }
 
-        game_tree.game_state.get_last_play().1.unwrap()
+    /// The stats of all the searches made by the bot: one list per play of the bot, with the
+    /// stats of each depth explored for this play
+    pub fn search_stats(&self) -> &[Vec<SearchStats>] {
+        &self.stats
     }
 
+    /// The average time the bot took to play, 0 if it did not play yet
     pub fn average_time(&self) -> u64 {
+        if self.times.is_empty() {
+            return 0;
+        }
         self.times.iter().sum::<u64>() / self.times.len() as u64
     }
 
+    /// The average number of times the bot explored a depth again because its weight was outside
+    /// of the aspiration window, per play (see `Aspiration`), 0 if it did not play yet
+    pub fn average_aspiration_researches(&self) -> f64 {
+        if self.stats.is_empty() {
+            return 0.0;
+        }
+        let total: u32 = self
+            .stats
+            .iter()
+            .map(|stats| aspiration_researches(stats))
+            .sum();
+        total as f64 / self.stats.len() as f64
+    }
+
     pub fn expect_game(&self) -> &G {
         self.game_tree.as_ref().unwrap().game()
     }
 }
+
+/// Whether the weight of a principal variation means a forced win or loss, which a deeper
+/// exploration would not change
+fn is_decided<S: Scalar>(variation: &[(impl Copy, S)]) -> bool {
+    !matches!(Outcome::decode(variation[0].1), Outcome::Heuristic(_))
+}
+
+/// The aspiration re-searches of all the depths explored for a play
+fn aspiration_researches(stats: &[SearchStats]) -> u32 {
+    stats.iter().map(|stats| stats.aspiration_researches).sum()
+}
+
+/// Runs `op` in `thread_pool`, or in the current one if None
+pub(crate) fn install<R: Send>(
+    thread_pool: Option<&ThreadPool>,
+    op: impl FnOnce() -> R + Send,
+) -> R {
+    match thread_pool {
+        Some(thread_pool) => thread_pool.install(op),
+        None => op(),
+    }
+}
@@ -0,0 +1,445 @@
+#[cfg(test)]
+mod bot_tests {
+    use std::num::{NonZeroU8, NonZeroUsize};
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::Relaxed;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::bot::{Bot, SearchLimit};
+    use crate::game::connect4::solver::{Position, Solver};
+    use crate::game::connect4::ConnectFour;
+    use crate::game::evaluator::Evaluator;
+    use crate::game::player::Player;
+    use crate::game::Game;
+    use crate::min_max::config::{Aspiration, SearchConfig};
+    use crate::min_max::node::GameNode;
+    use crate::min_max::outcome::Solution;
+
+    #[test]
+    fn move_time_is_respected() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut bot: Bot<ConnectFour> =
+            Bot::with_limit(p1, SearchLimit::MoveTime(Duration::from_millis(300)));
+
+        for _ in 0..3 {
+            let possible_plays = bot.expect_game().possible_plays();
+            let start = Instant::now();
+            let play = bot.play();
+            // the time stops the deepening long before the end of the game, and the bound on the
+            // clock only catches a search that ignores it, even on a slow machine
+            let stats = bot.search_stats().last().unwrap();
+            assert!(stats.len() < 20);
+            assert!(start.elapsed() < Duration::from_secs(5));
+            assert!(possible_plays.contains(&play));
+            let answer = bot.expect_game().possible_plays()[0];
+            bot.other_played(answer).unwrap();
+        }
+    }
+
+    #[test]
+    fn clock_is_consumed() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let limit = SearchLimit::Clock {
+            remaining: Duration::from_secs(2),
+            increment: Duration::ZERO,
+        };
+        let mut bot: Bot<ConnectFour> = Bot::with_limit(p1, limit);
+        bot.play();
+        match bot.limit {
+            SearchLimit::Clock { remaining, .. } => assert!(remaining < Duration::from_secs(2)),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn no_time_still_plays() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut bot: Bot<ConnectFour> = Bot::with_limit(p1, SearchLimit::MoveTime(Duration::ZERO));
+        let play = bot.play();
+        assert!(ConnectFour::new().possible_plays().contains(&play));
+    }
+
+    #[test]
+    #[should_panic(expected = "the game is over")]
+    fn no_play_after_the_end() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut bot: Bot<ConnectFour> = Bot::new(p1, 3);
+        let mut game = ConnectFour::new();
+        let mut player = p1;
+        // player 1 aligns 4 in the first column
+        for column in [1, 2, 1, 2, 1, 2, 1] {
+            game.play(player, NonZeroUsize::new(column).unwrap())
+                .unwrap();
+            player = player.other();
+        }
+        bot.game_tree = Some(GameNode::new_root(game, p1, 7));
+        bot.play();
+    }
+
+    #[test]
+    fn averages_before_playing() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let bot: Bot<ConnectFour> = Bot::new(p1, 3);
+        assert_eq!(bot.average_time(), 0);
+        assert_eq!(bot.average_aspiration_researches(), 0.0);
+    }
+
+    #[test]
+    fn cancel_from_other_thread() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut bot: Bot<ConnectFour> = Bot::new(p1, 40);
+        let cancellation = bot.cancellation_token();
+        let canceller = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            cancellation.cancel();
+        });
+
+        let start = Instant::now();
+        let play = bot.play();
+        canceller.join().unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(ConnectFour::new().possible_plays().contains(&play));
+
+        // the next play resets the token, and the tree can still be used
+        let answer = bot.expect_game().possible_plays()[0];
+        bot.other_played(answer).unwrap();
+        bot.limit = SearchLimit::Depth(4);
+        bot.play();
+    }
+
+    #[test]
+    fn cancellation_before_play_is_ignored() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut bot: Bot<ConnectFour> = Bot::new(p1, 3);
+        bot.cancellation_token().cancel();
+        bot.play();
+        let depths: Vec<u32> = bot.search_stats()[0]
+            .iter()
+            .map(|stats| stats.depth)
+            .collect();
+        assert_eq!(depths, vec![1, 2, 3]);
+    }
+
+    #[derive(Default)]
+    struct CountingEvaluator {
+        calls: AtomicUsize,
+    }
+
+    impl Evaluator<ConnectFour> for CountingEvaluator {
+        fn evaluate(&self, game: &ConnectFour, player: NonZeroU8) -> i32 {
+            self.calls.fetch_add(1, Relaxed);
+            game.get_score(player)
+        }
+    }
+
+    #[test]
+    fn custom_evaluator() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut bot: Bot<ConnectFour, CountingEvaluator> = Bot::new(p1, 3);
+        bot.play();
+        assert!(bot.evaluator.calls.load(Relaxed) > 0);
+    }
+
+    #[test]
+    fn make_unmake_bot_plays() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut bot: Bot<ConnectFour> = Bot::new(p1, 6).with_make_unmake();
+        for _ in 0..5 {
+            let possible_plays = bot.expect_game().possible_plays();
+            assert!(possible_plays.contains(&bot.play()));
+            // the bot may win against these answers before the end of the loop
+            if bot.expect_game().get_winner().is_some() {
+                break;
+            }
+            let answer = bot.expect_game().possible_plays()[0];
+            bot.other_played(answer).unwrap();
+        }
+    }
+
+    #[test]
+    fn bots_play_whole_games() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+        for _ in 0..3 {
+            // the trees are kept between the plays, so the nodes that were leaves of a previous
+            // search are explored again
+            let mut bots: [Bot<ConnectFour>; 2] = [Bot::new(p1, 4), Bot::new(p2, 4)];
+            let mut current = 0;
+            let mut game = ConnectFour::new();
+            while game.get_winner().is_none() && !game.is_full() {
+                let play = bots[current].play();
+                game.play(bots[current].player, play).unwrap();
+                bots[1 - current].other_played(play).unwrap();
+                current = 1 - current;
+            }
+            assert_eq!(bots[0].expect_game(), &game);
+            assert_eq!(bots[1].expect_game(), &game);
+        }
+    }
+
+    #[test]
+    fn threads_do_not_change_weight() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        for make_unmake in [false, true] {
+            let weights: Vec<_> = [1, 3]
+                .into_iter()
+                .map(|threads| {
+                    let mut bot: Bot<ConnectFour> = Bot::new(p1, 7).with_threads(threads);
+                    if make_unmake {
+                        bot = bot.with_make_unmake();
+                    }
+                    bot.play();
+                    bot.principal_variation()[0].1
+                })
+                .collect();
+            assert_eq!(weights[0], weights[1]);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_games() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+        let play_game = |seed: u64, make_unmake: bool| {
+            let new_bot = |player| {
+                // more threads than cores, so that they finish in a different order each time
+                let bot = Bot::new(player, 5)
+                    .with_config(SearchConfig::default().with_seed(seed))
+                    .with_threads(4);
+                if make_unmake {
+                    bot.with_make_unmake()
+                } else {
+                    bot
+                }
+            };
+            let mut bots: [Bot<ConnectFour>; 2] = [new_bot(p1), new_bot(p2)];
+            let mut plays = Vec::new();
+            let mut current = 0;
+            let mut game = ConnectFour::new();
+            while game.get_winner().is_none() && !game.is_full() {
+                let play = bots[current].play();
+                game.play(bots[current].player, play).unwrap();
+                bots[1 - current].other_played(play).unwrap();
+                plays.push(play.get());
+                current = 1 - current;
+            }
+            plays
+        };
+        for make_unmake in [false, true] {
+            for seed in 0..2 {
+                assert_eq!(play_game(seed, make_unmake), play_game(seed, make_unmake));
+            }
+        }
+    }
+
+    #[test]
+    fn draw_is_expected_whatever_the_contempt() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        // only column 4 is left, with 3 cells, and filling it is a draw
+        let mut game = ConnectFour::new();
+        let mut player = p1;
+        for column in "547125662261271266215743771576315353334".chars() {
+            game.play(
+                player,
+                NonZeroUsize::new(column as usize - '0' as usize).unwrap(),
+            )
+            .unwrap();
+            player = player.other();
+        }
+        // from a draw as bad as a loss to a draw as good as a win
+        for contempt in [i32::MAX, 0, -i32::MAX] {
+            // explores 5 plays ahead, past the end of the game
+            let config = SearchConfig::default()
+                .with_contempt(contempt)
+                .with_endgame_plays(0);
+            let mut bot: Bot<ConnectFour> = Bot::new(player, 5).with_config(config);
+            bot.game_tree = Some(GameNode::new_root(game.clone(), player, 39));
+            bot.play();
+            assert_eq!(bot.principal_variation()[0].1, config.draw_weight());
+            assert!(bot.expects_draw(game.clone()));
+            assert_eq!(bot.solution(), Some(Solution::Draw));
+        }
+        let mut bot: Bot<ConnectFour> = Bot::new(p1, 3);
+        let game = bot.expect_game().clone();
+        bot.play();
+        assert!(!bot.expects_draw(game));
+    }
+
+    #[test]
+    fn endgame_is_solved() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut solver = Solver::default();
+        for line in include_str!("../game/connect4/solver_positions.txt").lines() {
+            let Some((columns, _)) = line.split_once(' ').filter(|_| !line.starts_with('#')) else {
+                continue;
+            };
+            // at most 18 plays left
+            if columns.len() < 24 {
+                continue;
+            }
+            let mut game = ConnectFour::new();
+            let mut player = p1;
+            for column in columns.chars() {
+                let column = column.to_digit(10).unwrap() as usize;
+                game.play(player, NonZeroUsize::new(column).unwrap())
+                    .unwrap();
+                player = player.other();
+            }
+            let expected = solver.solve(&Position::from_columns(columns).unwrap());
+            for endgame_plays in [0, 18] {
+                // enough time to reach the end even in a slow build
+                let config = SearchConfig::default()
+                    .with_endgame_plays(endgame_plays)
+                    .with_endgame_time(Duration::from_secs(60));
+                let mut bot: Bot<ConnectFour> = Bot::new(player, 2).with_config(config);
+                let depth = columns.len() as u32;
+                bot.game_tree = Some(GameNode::new_root(game.clone(), player, depth));
+                bot.play();
+                if endgame_plays == 0 {
+                    // exploring 2 plays ahead only proves the closest wins and losses
+                    assert!(bot.solution().is_none() || bot.solution() == Some(expected));
+                } else {
+                    assert_eq!(bot.solution(), Some(expected), "plays {columns}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn endgame_respects_move_time() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let config = SearchConfig::default().with_endgame_plays(42);
+        let limit = SearchLimit::MoveTime(Duration::from_millis(200));
+        let mut bot: Bot<ConnectFour> = Bot::with_limit(p1, limit).with_config(config);
+        let start = Instant::now();
+        bot.play();
+        // only catches a search that ignores the time, even on a slow machine
+        assert!(start.elapsed() < Duration::from_secs(5));
+        // the empty board is too far from the end to be solved in time
+        assert_eq!(bot.solution(), None);
+        let stats = bot.search_stats().last().unwrap();
+        assert!(stats.len() < 42);
+    }
+
+    #[test]
+    fn endgame_time_bounds_depth_limit() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let config = SearchConfig::default()
+            .with_endgame_plays(42)
+            .with_endgame_time(Duration::from_millis(100));
+        let mut bot: Bot<ConnectFour> = Bot::new(p1, 3).with_config(config);
+        let start = Instant::now();
+        bot.play();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        // the depth is explored, but not the end of the game
+        assert_eq!(bot.solution(), None);
+        let stats = bot.search_stats().last().unwrap();
+        assert!(stats[..3].iter().all(|stats| stats.completed));
+    }
+
+    #[test]
+    fn search_stats_of_each_play() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut bot: Bot<ConnectFour> = Bot::new(p1, 3);
+        for _ in 0..2 {
+            let play = bot.play();
+            bot.other_played(play).unwrap();
+        }
+        let stats = bot.search_stats();
+        assert_eq!(stats.len(), 2);
+        for play_stats in stats {
+            let depths: Vec<u32> = play_stats.iter().map(|stats| stats.depth).collect();
+            assert_eq!(depths, vec![1, 2, 3]);
+            assert!(play_stats
+                .iter()
+                .all(|stats| stats.completed && stats.nodes > 0));
+        }
+    }
+
+    #[test]
+    fn aspiration_windows_do_not_change_plays() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        for make_unmake in [false, true] {
+            let bots: Vec<Bot<ConnectFour>> = [
+                Aspiration::None,
+                Aspiration::Window {
+                    initial_width: 1,
+                    growth: 2,
+                },
+            ]
+            .into_iter()
+            .map(|aspiration| {
+                let config = SearchConfig::default()
+                    .with_seed(1)
+                    .with_aspiration(aspiration);
+                let mut bot = Bot::new(p1, 5).with_config(config);
+                if make_unmake {
+                    bot = bot.with_make_unmake();
+                }
+                for _ in 0..3 {
+                    let play = bot.play();
+                    bot.other_played(play).unwrap();
+                }
+                bot
+            })
+            .collect();
+            assert_eq!(bots[0].expect_game(), bots[1].expect_game());
+            // the rest of the principal variations may be cut by the table at different places
+            assert_eq!(
+                bots[0].principal_variation()[0],
+                bots[1].principal_variation()[0]
+            );
+            assert_eq!(bots[0].average_aspiration_researches(), 0.0);
+            assert!(bots[1].average_aspiration_researches() > 0.0);
+        }
+    }
+
+    #[test]
+    fn principal_variation_starts_with_play() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        for make_unmake in [false, true] {
+            let mut bot: Bot<ConnectFour> = Bot::new(p1, 5);
+            if make_unmake {
+                bot = bot.with_make_unmake();
+            }
+            for _ in 0..3 {
+                let mut game = bot.expect_game().clone();
+                let play = bot.play();
+                let variation = bot.principal_variation();
+                assert_eq!(variation[0].0, play);
+                assert!(variation.len() <= 5);
+                // the expected plays can be played one after the other
+                let mut player = p1;
+                for (play, _) in variation {
+                    game.play(player, *play).unwrap();
+                    player = player.other();
+                }
+
+                let answer = bot.expect_game().possible_plays()[0];
+                bot.other_played(answer).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn analysis_does_not_play() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        for make_unmake in [false, true] {
+            let mut bot: Bot<ConnectFour> = Bot::new(p1, 4);
+            if make_unmake {
+                bot = bot.with_make_unmake();
+            }
+            let analysis = bot.analyze(4).unwrap();
+            assert_eq!(analysis.len(), ConnectFour::WIDTH);
+            assert_eq!(bot.expect_game(), &ConnectFour::new());
+
+            // the bot plays one of the best rated plays
+            let play = bot.play();
+            let best_weight = analysis[0].weight;
+            assert!(analysis
+                .iter()
+                .any(|analyzed| analyzed.play == play && analyzed.weight == best_weight));
+        }
+    }
+}
@@ -5,8 +5,9 @@ use crate::game::player::Player;
 use crate::scalar::Scalar;
 
 pub mod connect4;
+pub mod evaluator;
 pub mod player;
-pub(crate) mod state;
+pub mod state;
 
 pub trait Game: Clone + Send + Sync {
     type Coordinate;
@@ -35,10 +36,40 @@ pub trait Game: Clone + Send + Sync {
 
     fn possible_plays(&self) -> Vec<Self::InputCoordinate>;
 
+    /**
+     * Reorders `plays`, as returned by `possible_plays`, among the plays that are expected to be
+     * as good as each other, with `random` as the source of randomness. The same `random` gives
+     * the same order. Does nothing by default.
+     */
+    fn shuffle_plays(&self, _plays: &mut [Self::InputCoordinate], _random: u64) {}
+
     fn print(&self);
 
     /**
      * Last play made in the game. None only if no play has been made yet.
      */
     fn last_play(&self) -> Option<Self::InputCoordinate>;
+
+    /**
+     * Cancels the last play, so that the game is exactly as it was before it. Returns the
+     * cancelled play, or None if no play has been made yet.
+     *
+     * The game is only guaranteed to be restored if there was no winner before the last play.
+     */
+    fn undo(&mut self) -> Option<Self::InputCoordinate>;
+
+    /**
+     * Hash of the position, kept up to date by `play`. Two games with the same board have the
+     * same key, no matter in which order the plays were made.
+     */
+    fn hash_key(&self) -> u64;
+
+    /**
+     * The most plays that can be made before the end of the game, if known, so that the bot can
+     * explore until the end when few are left (see `SearchConfig::with_endgame_plays`). None by
+     * default.
+     */
+    fn remaining_plays(&self) -> Option<u32> {
+        None
+    }
 }
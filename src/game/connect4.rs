@@ -2,132 +2,158 @@ use std::cmp::min;
 use std::num::{NonZeroU8, NonZeroUsize};
 
 use console::Style;
-use rand::Rng;
 
 use crate::game::connect4::count_direction::CountDirection;
+use crate::game::connect4::evaluator::AlignmentEvaluator;
 use crate::game::connect4::iteration::{BoardIterator, P4IteratorType};
+use crate::game::evaluator::Evaluator;
 use crate::game::Game;
 
+pub mod bitboard;
 mod count_direction;
+pub mod evaluator;
 mod iteration;
+pub mod solver;
 mod tests;
 
-#[derive(Debug, Clone)]
-pub struct ConnectFour {
-    board: [[Option<NonZeroU8>; 7]; 6],
+/// The usual game: 7 columns, 6 rows and 4 in a row to win
+pub type ConnectFour = ConnectN<7, 6, 4>;
+
+/**
+ * Connect-N game with `W` columns and `H` rows, won by aligning `N` cells.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectN<const W: usize, const H: usize, const N: usize> {
+    board: [[Option<NonZeroU8>; W]; H],
     last_played_coords: Option<(usize, usize)>,
     winner: Option<NonZeroU8>,
+    /// Lines of `N - 2` cells (2 in ConnectFour)
     p1_aligns2: u16,
+    /// Lines of `N - 1` cells (3 in ConnectFour)
     p1_aligns3: u16,
     p2_aligns2: u16,
     p2_aligns3: u16,
+    /// Zobrist hash of the board, see `ZOBRIST_KEYS`
+    hash: u64,
+    /// Columns played, from the first play to the last one, to be able to undo them. Play `i` is
+    /// at `history[i / W][i % W]`
+    history: [[u8; W]; H],
+    play_count: u8,
 }
 
-impl ConnectFour {
+impl<const W: usize, const H: usize, const N: usize> ConnectN<W, H, N> {
+    pub const WIDTH: usize = W;
+    pub const HEIGHT: usize = H;
+    /// Number of aligned cells needed to win
+    pub const CONNECT: usize = N;
+
     /**
-     * Returns all iterators for all lines having 4 or more cells
+     * Returns all iterators for all lines having `N` or more cells
      */
-    fn all_lines_longer_4(&self) -> Vec<BoardIterator> {
-        let mut iterators: Vec<BoardIterator> = Vec::with_capacity(7 + 6 + 2 * 7); // 7 horizontal + 6 vertical + 2 * 7 diagonal
+    #[allow(dead_code)]
+    fn all_lines_longer_n(&self) -> Vec<BoardIterator<'_, W, H, N>> {
+        let (width, height, connect) = (W as isize, H as isize, N as isize);
+        let mut iterators: Vec<BoardIterator<W, H, N>> = Vec::with_capacity(H + W + 2 * W); // H horizontal + W vertical + 2 * W diagonal
 
-        for y in 0..6 {
+        for y in 0..height {
             iterators.push(BoardIterator::new_at(
-                &self,
+                self,
                 P4IteratorType::Horizontal,
                 0,
                 y,
             ));
         }
 
-        for x in 0..7 {
-            iterators.push(BoardIterator::new_at(&self, P4IteratorType::Vertical, x, 0));
+        for x in 0..width {
+            iterators.push(BoardIterator::new_at(self, P4IteratorType::Vertical, x, 0));
         }
 
-        // Diagonal down -> starting here:
+        // Diagonal down -> starting here (in ConnectFour):
         // X  X  X  X  -  -  -
         // X  -  -  -  -  -  -
         // X  -  -  -  -  -  -
         // -  -  -  -  -  -  -
         // -  -  -  -  -  -  -
         // -  -  -  -  -  -  -
-        for x in 0..=3 {
+        for x in 0..=width - connect {
             iterators.push(BoardIterator::new_at(
-                &self,
+                self,
                 P4IteratorType::DiagonalDown,
                 x,
                 0,
             ));
         }
-        for y in 1..=3 {
+        for y in 1..=height - connect {
             iterators.push(BoardIterator::new_at(
-                &self,
+                self,
                 P4IteratorType::DiagonalDown,
                 0,
                 y,
             ));
         }
 
-        // Diagonal up -> starting here:
+        // Diagonal up -> starting here (in ConnectFour):
         // -  -  -  -  -  -  -
         // -  -  -  -  -  -  -
         // -  -  -  -  -  -  -
         // X  -  -  -  -  -  -
         // X  -  -  -  -  -  -
         // X  X  X  X  -  -  -
-        for y in 3..=5 {
+        for y in connect - 1..height {
             iterators.push(BoardIterator::new_at(
-                &self,
+                self,
                 P4IteratorType::DiagonalUp,
                 0,
                 y,
             ));
         }
-        for x in 1..=3 {
+        for x in 1..=width - connect {
             iterators.push(BoardIterator::new_at(
-                &self,
+                self,
                 P4IteratorType::DiagonalUp,
                 x,
-                5,
+                height - 1,
             ));
         }
 
         iterators
     }
 
-    fn lines_passing_at_longer_4(
+    fn lines_passing_at_longer_n(
         &self,
-        coords: <ConnectFour as Game>::Coordinate,
-    ) -> Vec<BoardIterator> {
+        coords: <Self as Game>::Coordinate,
+    ) -> Vec<BoardIterator<'_, W, H, N>> {
         let y = coords.0 as isize;
         let x = coords.1 as isize;
+        let connect_minus1 = N as isize - 1;
         let mut iterators = Vec::with_capacity(4);
 
         iterators.push(BoardIterator::new_at(
-            &self,
+            self,
             P4IteratorType::Horizontal,
             0,
             y,
         ));
-        iterators.push(BoardIterator::new_at(&self, P4IteratorType::Vertical, x, 0));
+        iterators.push(BoardIterator::new_at(self, P4IteratorType::Vertical, x, 0));
 
-        let x_from_left = 6 - x;
-        let y_from_bottom = 5 - y;
+        let x_from_left = W as isize - 1 - x;
+        let y_from_bottom = H as isize - 1 - y;
 
-        if x + y_from_bottom >= 3 && x_from_left + y >= 3 {
+        if x + y_from_bottom >= connect_minus1 && x_from_left + y >= connect_minus1 {
             let subtract = min(x, y);
             // one of x - subtract or y - subtract is 0
             iterators.push(BoardIterator::new_at(
-                &self,
+                self,
                 P4IteratorType::DiagonalDown,
                 x - subtract,
                 y - subtract,
             ));
         }
 
-        if x + y >= 3 && x_from_left + y_from_bottom >= 3 {
+        if x + y >= connect_minus1 && x_from_left + y_from_bottom >= connect_minus1 {
             let subtract = min(x, y_from_bottom);
             iterators.push(BoardIterator::new_at(
-                &self,
+                self,
                 P4IteratorType::DiagonalUp,
                 x - subtract,
                 y + subtract,
@@ -136,12 +162,8 @@ impl ConnectFour {
         iterators
     }
 
-    fn calculate_score(&self, aligns2: u16, aligns3: u16) -> i32 {
-        (10 * aligns2 + 100 * aligns3) as i32
-    }
-
     pub fn get_isize(&self, (row, column): (isize, isize)) -> Option<NonZeroU8> {
-        if row < 0 || row >= 6 || column < 0 || column >= 7 {
+        if row < 0 || row >= H as isize || column < 0 || column >= W as isize {
             return None;
         }
         self.board[row as usize][column as usize]
@@ -151,13 +173,10 @@ impl ConnectFour {
         self.play(player, NonZeroUsize::new(column).unwrap())
     }
 
-    pub fn get_winner_coords(&self) -> Option<[<Self as Game>::Coordinate; 4]> {
-        if self.last_played_coords.is_none() {
-            return None;
-        }
-        let last_coords = self.last_played_coords.unwrap();
-        for mut line_iterator in self.lines_passing_at_longer_4(last_coords) {
-            let mut winner_coords: Vec<(isize, isize)> = Vec::with_capacity(2 * 4 - 1);
+    pub fn get_winner_coords(&self) -> Option<[<Self as Game>::Coordinate; N]> {
+        let last_coords = self.last_played_coords?;
+        for mut line_iterator in self.lines_passing_at_longer_n(last_coords) {
+            let mut winner_coords: Vec<(isize, isize)> = Vec::with_capacity(2 * N - 1);
             let mut strike_player = NonZeroU8::new(1u8).unwrap();
             let mut strike: u8 = 0;
             let mut cell_option = line_iterator.get_with_offset(0);
@@ -167,11 +186,11 @@ impl ConnectFour {
                     if strike_player == cell_player {
                         strike += 1;
 
-                        if strike == 4 {
-                            let mut result = [(0usize, 0usize); 4];
+                        if strike as usize == N {
+                            let mut result = [(0usize, 0usize); N];
                             let winner_coords_size = winner_coords.len();
-                            for i in 0..4 {
-                                let (y, x) = winner_coords[winner_coords_size - 4 + i];
+                            for i in 0..N {
+                                let (y, x) = winner_coords[winner_coords_size - N + i];
                                 result[i] = (y as usize, x as usize);
                             }
                             return Some(result);
@@ -192,7 +211,7 @@ impl ConnectFour {
 
     fn count_in_direction(
         &self,
-        start: <ConnectFour as Game>::Coordinate,
+        start: <Self as Game>::Coordinate,
         direction: CountDirection,
         max: u8,
     ) -> u8 {
@@ -202,8 +221,8 @@ impl ConnectFour {
         }
         let mut count: u8 = 0;
         let player_to_count = player_to_count.unwrap();
-        let mut coords = direction.add_to(start);
-        while let Some(player) = coords.map(|coords| self.get(coords)).flatten() {
+        let mut coords = direction.add_to::<W, H>(start);
+        while let Some(player) = coords.and_then(|coords| self.get(coords)) {
             if player == player_to_count {
                 count += 1;
                 if count == max {
@@ -212,135 +231,149 @@ impl ConnectFour {
             } else {
                 break;
             }
-            coords = direction.add_to(coords.unwrap());
+            coords = direction.add_to::<W, H>(coords.unwrap());
         }
         count
     }
 
-    fn compute_aligments(&mut self) {
-        /*
-        let mut p1_aligns2 = 0;
-        let mut p1_aligns3 = 0;
-        let mut p2_aligns2 = 0;
-        let mut p2_aligns3 = 0;
-
-        // let debug_cell = |cell: Option<Option<NonZeroU8>>| cell.map(|c| c.map(|c| c.to_string()).unwrap_or("-".to_string())).unwrap_or("X".to_string());
-        let is_playable =
-            |cell: Option<Option<NonZeroU8>>| cell.is_some() && cell.unwrap().is_none();
-        for mut line_iterator in self.all_lines_longer_4() {
-            let mut strike_player = 0u8; // this value is never used
-            let mut strike: u8 = 0;
-            let mut cell_option = line_iterator.get_with_offset(0);
-            while let Some(cell) = cell_option {
-                if let Some(cell_player) = cell {
-                    let cell_player_u8 = cell_player.get();
-                    if strike_player == cell_player_u8 {
-                        let before3 = || line_iterator.get_with_offset(-3);
-                        let before2 = || line_iterator.get_with_offset(-2);
-                        // let before1 = || line_iterator.get_with_offset(-1);
-                        let after1 = || line_iterator.get_with_offset(1);
-                        let after2 = || line_iterator.get_with_offset(2);
-
-                        strike += 1;
-
-                        match strike {
-                            Self::CONNECT => {
-                                self.winner = Some(cell_player);
-                                return;
-                            }
-                            2 => {
-                                if (is_playable(before3()) && is_playable(before2())) // space 2 before
-                                    || (is_playable(after1()) && is_playable(after2()))
-                                // space 2 after
-                                {
-                                    if strike_player == 1u8 {
-                                        p1_aligns2 += 1;
-                                    } else {
-                                        p2_aligns2 += 1;
-                                    }
-                                }
-                            }
-                            3 => {
-                                if is_playable(before3()) // space 1 before
-                                    || is_playable(after1()) // space 1 after
-                                {
-                                    if strike_player == 1u8 {
-                                        p1_aligns3 += 1;
-                                    } else {
-                                        p2_aligns3 += 1;
-                                    }
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else {
-                        strike_player = cell_player_u8;
-
-                        strike = 1;
-                    }
-                } else {
-                    strike = 0;
-                }
-                cell_option = line_iterator.next();
-            }
-        }
-        self.p1_aligns2 = p1_aligns2;
-        self.p1_aligns3 = p1_aligns3;
-        self.p2_aligns2 = p2_aligns2;
-        self.p2_aligns3 = p2_aligns3;
-
-         */
+    /// Updates the alignments and the winner after a play at `last_played_coords`, or before
+    /// cancelling it if `undo` is true (the cell must then still be filled)
+    fn compute_aligments(&mut self, undo: bool) {
         if self.last_played_coords.is_none() {
             return;
         }
         let last_coords = self.last_played_coords.unwrap();
         let counting_player = *self.get(last_coords).unwrap();
+        let connect_minus1 = N as u8 - 1;
         for count_direction in CountDirection::half_side() {
-            // max is 3 (4 - 1) because we don't count the middle/start cell
-            let count = self.count_in_direction(last_coords, count_direction, 3);
-            if count == 3 {
-                self.winner = Some(counting_player);
-                return;
-            }
-            let count_opposite =
-                self.count_in_direction(last_coords, count_direction.opposite(), 3 - count);
-            if count + count_opposite == 3 {
-                self.winner = Some(counting_player);
+            // max is N - 1 because we don't count the middle/start cell
+            let count = self.count_in_direction(last_coords, count_direction, connect_minus1);
+            let count_opposite = if count == connect_minus1 {
+                0
+            } else {
+                self.count_in_direction(
+                    last_coords,
+                    count_direction.opposite(),
+                    connect_minus1 - count,
+                )
+            };
+            if count + count_opposite == connect_minus1 {
+                // the play won, the alignments of the next directions were not updated
+                self.winner = if undo { None } else { Some(counting_player) };
                 return;
             }
 
-            let counting_player_u8 = counting_player.get();
+            let (aligns2, aligns3) = if counting_player.get() == 1u8 {
+                (&mut self.p1_aligns2, &mut self.p1_aligns3)
+            } else {
+                (&mut self.p2_aligns2, &mut self.p2_aligns3)
+            };
 
-            if count == 2 || count_opposite == 2 {
-                // implies that the other count is <= 1
-                // total: 3
-                if counting_player_u8 == 1u8 {
-                    self.p1_aligns2 -= 1;
-                    self.p1_aligns3 += 1;
+            // the play joins the lines of `count` and `count_opposite` cells on each side into a
+            // single line, so that the alignments only depend on the board and not on the order
+            // of the plays
+            let lines = [
+                (count, undo),
+                (count_opposite, undo),
+                (count + count_opposite + 1, !undo),
+            ];
+            for (length, added) in lines {
+                let aligns = if length + 2 == N as u8 {
+                    &mut *aligns2
+                } else if length + 1 == N as u8 {
+                    &mut *aligns3
                 } else {
-                    self.p2_aligns2 -= 1;
-                    self.p2_aligns3 += 1;
-                }
-            } else if count + count_opposite == 1 {
-                // total: 2
-                if counting_player_u8 == 1u8 {
-                    self.p1_aligns2 += 1;
+                    continue;
+                };
+                if added {
+                    *aligns += 1;
                 } else {
-                    self.p2_aligns2 += 1;
+                    *aligns -= 1;
+                }
+            }
+        }
+    }
+
+    /// Row of the highest filled cell of a column, if any
+    fn top_filled_row(&self, column: usize) -> Option<usize> {
+        (0..H).find(|&y| self.board[y][column].is_some())
+    }
+
+    /// Order in which the columns (from 1 to `W`) are returned by `possible_plays`: from the
+    /// center to the sides, as the center ones are usually the best
+    fn play_order() -> [usize; W] {
+        let mut order: [usize; W] = std::array::from_fn(|i| i + 1);
+        order.sort_by_key(|&column| Self::center_distance(column));
+        order
+    }
+
+    fn center_distance(column: usize) -> usize {
+        (2 * column).abs_diff(W + 1)
+    }
+
+    /// `Game::shuffle_plays` for the columns in the order of `play_order`: the columns at the same
+    /// distance of the center are swapped or not, depending on a bit of `random` for each pair
+    fn shuffle_columns(plays: &mut [NonZeroUsize], random: u64) {
+        let mut pair = 0;
+        let mut i = 0;
+        while i + 1 < plays.len() {
+            if Self::center_distance(plays[i].get()) == Self::center_distance(plays[i + 1].get()) {
+                if random >> (pair % 64) & 1 == 1 {
+                    plays.swap(i, i + 1);
                 }
+                pair += 1;
+                i += 2;
+            } else {
+                i += 1;
             }
         }
     }
 
-    const RANDOMIZE_POSSIBLE_PLAYS: bool = true;
+    fn played_column(&self, index: usize) -> usize {
+        self.history[index / W][index % W] as usize
+    }
+
+    fn set_played_column(&mut self, index: usize, column: usize) {
+        self.history[index / W][index % W] = column as u8;
+    }
+
+    /// One random key per (row, column, player), the hash of a board is the xor of the keys of
+    /// all its cells
+    const ZOBRIST_KEYS: [[[u64; 2]; W]; H] = {
+        let mut keys = [[[0; 2]; W]; H];
+        let mut state: u64 = 0x4d595df4d0f33173;
+        let mut y = 0;
+        while y < H {
+            let mut x = 0;
+            while x < W {
+                let mut p = 0;
+                while p < 2 {
+                    // splitmix64
+                    state = state.wrapping_add(0x9e3779b97f4a7c15);
+                    let mut z = state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+                    keys[y][x][p] = z ^ (z >> 31);
+                    p += 1;
+                }
+                x += 1;
+            }
+            y += 1;
+        }
+        keys
+    };
+
+    fn zobrist_key(y: usize, x: usize, player: NonZeroU8) -> u64 {
+        Self::ZOBRIST_KEYS[y][x][player.get() as usize - 1]
+    }
 }
 
-impl Game for ConnectFour {
-    /// (row, column) or (y, x). Starts at (0, 0) at the top left corner and ends at (5, 6) at the
-    /// bottom right corner
+impl<const W: usize, const H: usize, const N: usize> Game for ConnectN<W, H, N> {
+    /// (row, column) or (y, x). Starts at (0, 0) at the top left corner and ends at
+    /// (H - 1, W - 1) at the bottom right corner
     type Coordinate = (usize, usize);
 
-    /// an usize from 1 to 7
+    /// an usize from 1 to W
     type InputCoordinate = NonZeroUsize;
 
     /**
@@ -350,20 +383,32 @@ impl Game for ConnectFour {
 
     type Score = i32;
 
-    fn new() -> ConnectFour {
-        ConnectFour {
-            board: [[None; 7]; 6],
+    fn new() -> Self {
+        // play_count and the columns of history are u8
+        const { assert!(W * H <= u8::MAX as usize, "more than 255 cells") };
+        // the scores count the lines of N - 2 cells, and a line of N cells must fit in the board
+        const {
+            assert!(
+                N >= 3 && (N <= W || N <= H),
+                "N must be from 3 to the board size"
+            )
+        };
+        ConnectN {
+            board: [[None; W]; H],
             last_played_coords: None,
             winner: None,
             p1_aligns2: 0,
             p1_aligns3: 0,
             p2_aligns2: 0,
             p2_aligns3: 0,
+            hash: 0,
+            history: [[0; W]; H],
+            play_count: 0,
         }
     }
 
     fn get(&self, (row, column): (usize, usize)) -> Option<&NonZeroU8> {
-        if row >= 6 || column >= 7 {
+        if row >= H || column >= W {
             return None;
         }
         self.board[row][column].as_ref()
@@ -371,15 +416,17 @@ impl Game for ConnectFour {
 
     fn play<'a>(&mut self, player: NonZeroU8, column: NonZeroUsize) -> Result<(), &'a str> {
         let column_min1 = column.get() - 1;
-        if column_min1 >= 7 {
+        if column_min1 >= W {
             return Err("Column out of bounds");
         }
-        for i in 0..6 {
-            let y = 5 - i;
+        for y in (0..H).rev() {
             if self.board[y][column_min1].is_none() {
                 self.board[y][column_min1] = Some(player);
+                self.hash ^= Self::zobrist_key(y, column_min1, player);
                 self.last_played_coords = Some((y, column_min1));
-                self.compute_aligments();
+                self.set_played_column(self.play_count as usize, column_min1);
+                self.play_count += 1;
+                self.compute_aligments(false);
                 return Ok(());
             }
         }
@@ -387,63 +434,18 @@ impl Game for ConnectFour {
     }
 
     /**
-     * Returns the score of the player, higher is better
-     *
-     * Scores:
-     * - 2 aligned: 20n (n = number of 2 aligned)
-     * - 3 aligned: 100n (n = number of 3 aligned)
-     * - 4 aligned: infinite
-     * Subtract the same score for the opponent
-     * Scores are invalid if the line cannot be completed
+     * Returns the score of the player, higher is better, see `AlignmentEvaluator`
      */
     fn get_score(&self, player: Self::Player) -> Self::Score {
-        if let Some(winner) = self.winner {
-            return if winner == player {
-                Self::Score::MAX
-            } else {
-                Self::Score::MIN
-            };
-        }
-        let p1_score = self.calculate_score(self.p1_aligns2, self.p1_aligns3)
-            - self.calculate_score(self.p2_aligns2, self.p2_aligns3);
-        if player.get() == 1u8 {
-            p1_score
-        } else {
-            -p1_score
-        }
+        AlignmentEvaluator::default().evaluate(self, player)
     }
 
     fn get_winner(&self) -> Option<Self::Player> {
-        /*
-        if self.last_played_coords.is_none() {
-            return None;
-        }
-        let last_coords = self.last_played_coords.unwrap();
-        let counting_player = *self.get(last_coords).unwrap();
-        let connect_minus1 = Self::CONNECT - 1;
-        for count_direction in CountDirection::half_side() {
-            // max is 3 because we don't count the middle/start cell
-            let count = self.count_in_direction(last_coords, count_direction, connect_minus1);
-            if count == connect_minus1 {
-                return Some(counting_player);
-            }
-            let count_opposite = self.count_in_direction(
-                last_coords,
-                count_direction.opposite(),
-                connect_minus1 - count,
-            );
-            if count + count_opposite == connect_minus1 {
-                return Some(counting_player);
-            }
-        }
-        None
-
-         */
         self.winner
     }
 
     fn is_full(&self) -> bool {
-        for i in 0..7 {
+        for i in 0..W {
             if self.board[0][i].is_none() {
                 return false;
             }
@@ -452,23 +454,18 @@ impl Game for ConnectFour {
     }
 
     fn possible_plays(&self) -> Vec<NonZeroUsize> {
-        let order: [usize; 7] = if Self::RANDOMIZE_POSSIBLE_PLAYS {
-            match rand::thread_rng().gen_range(0..=2) {
-                0 => [4, 3, 5, 2, 6, 1, 7],
-                1 => [3, 5, 4, 6, 2, 1, 7],
-                _ => [6, 2, 4, 5, 3, 1, 7],
-            }
-        } else {
-            [4, 3, 5, 2, 6, 1, 7]
-        };
-        let mut vec: Vec<NonZeroUsize> = Vec::with_capacity(7);
-        order
+        let mut vec: Vec<NonZeroUsize> = Vec::with_capacity(W);
+        Self::play_order()
             .iter()
             .filter(|&column| self.get((0, column - 1)).is_none())
             .for_each(|&column| vec.push(NonZeroUsize::new(column).unwrap()));
         vec
     }
 
+    fn shuffle_plays(&self, plays: &mut [NonZeroUsize], random: u64) {
+        Self::shuffle_columns(plays, random)
+    }
+
     fn print(&self) {
         let p1_color = Style::new().red();
         let p2_color = Style::new().blue();
@@ -485,9 +482,10 @@ impl Game for ConnectFour {
             style
         };
 
-        println!("1 2 3 4 5 6 7");
-        for y in 0..6 {
-            for x in 0..7 {
+        let columns: Vec<String> = (1..=W).map(|column| column.to_string()).collect();
+        println!("{}", columns.join(" "));
+        for y in 0..H {
+            for x in 0..W {
                 let cell_str = self.board[y][x]
                     .map(|cell| cell.to_string())
                     .unwrap_or("-".to_string());
@@ -508,6 +506,30 @@ impl Game for ConnectFour {
 
     fn last_play(&self) -> Option<Self::InputCoordinate> {
         self.last_played_coords
-            .map(|(x, _)| NonZeroUsize::new(x).unwrap())
+            .map(|(_, x)| NonZeroUsize::new(x + 1).unwrap())
+    }
+
+    fn undo(&mut self) -> Option<Self::InputCoordinate> {
+        let (y, x) = self.last_played_coords?;
+        self.compute_aligments(true);
+        let player = self.board[y][x].take().unwrap();
+        self.hash ^= Self::zobrist_key(y, x, player);
+        self.play_count -= 1;
+        self.set_played_column(self.play_count as usize, 0);
+        self.last_played_coords = if self.play_count == 0 {
+            None
+        } else {
+            let column = self.played_column(self.play_count as usize - 1);
+            Some((self.top_filled_row(column).unwrap(), column))
+        };
+        NonZeroUsize::new(x + 1)
+    }
+
+    fn hash_key(&self) -> u64 {
+        self.hash
+    }
+
+    fn remaining_plays(&self) -> Option<u32> {
+        Some((W * H) as u32 - self.play_count as u32)
     }
 }
@@ -0,0 +1,250 @@
+use std::num::{NonZeroU8, NonZeroUsize};
+
+use crate::game::connect4::evaluator::AlignmentEvaluator;
+use crate::game::connect4::ConnectN;
+use crate::game::evaluator::Evaluator;
+use crate::game::Game;
+
+/// `get` returns references to the players, which are not stored in the board
+static PLAYERS: [NonZeroU8; 2] = [NonZeroU8::new(1).unwrap(), NonZeroU8::new(2).unwrap()];
+
+pub type BitBoardConnectFour = BitBoardConnectN<7, 6, 4>;
+
+/**
+ * Same game as `ConnectN`, but the board is stored as one bit mask per player, which makes
+ * plays, win detection and alignment counting a few shifts and popcounts.
+ *
+ * Bit `(H + 1) * column + height` is the cell at `height` from the bottom of `column`. The last
+ * bit of each column is always empty, so that lines never continue from the top of a column to
+ * the bottom of the next one. The board must then fit in 64 bits: `W * (H + 1) <= 64`.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitBoardConnectN<const W: usize, const H: usize, const N: usize> {
+    /// Cells of player 1 and player 2
+    masks: [u64; 2],
+    /// Number of filled cells in each column
+    heights: [u8; W],
+    winner: Option<NonZeroU8>,
+    /// Same hash as `ConnectN`, see `ConnectN::ZOBRIST_KEYS`
+    hash: u64,
+    /// Columns played, from the first play to the last one, to be able to undo them. Play `i` is
+    /// at `history[i / W][i % W]`
+    history: [[u8; W]; H],
+    play_count: u8,
+}
+
+impl<const W: usize, const H: usize, const N: usize> BitBoardConnectN<W, H, N> {
+    /// Shifts going from a cell to the next one in a line: vertical, horizontal and both
+    /// diagonals
+    const DIRECTIONS: [u32; 4] = [1, H as u32 + 1, H as u32, H as u32 + 2];
+
+    fn bit(y: usize, x: usize) -> u64 {
+        1 << ((H + 1) * x + H - 1 - y)
+    }
+
+    /// `mask >> shift`, but 0 instead of overflowing if all bits are shifted out
+    fn shift_right(mask: u64, shift: u32) -> u64 {
+        mask.checked_shr(shift).unwrap_or(0)
+    }
+
+    fn played_column(&self, index: usize) -> usize {
+        self.history[index / W][index % W] as usize
+    }
+
+    fn set_played_column(&mut self, index: usize, column: usize) {
+        self.history[index / W][index % W] = column as u8;
+    }
+
+    fn player_index(player: NonZeroU8) -> usize {
+        player.get() as usize - 1
+    }
+
+    fn has_line(mask: u64) -> bool {
+        Self::count_lines_at_least(mask, N as u32) != 0
+    }
+
+    /// Starts of `length` or more aligned cells of the mask, in each direction
+    fn line_starts(mask: u64, length: u32) -> impl Iterator<Item = (u32, u64)> {
+        Self::DIRECTIONS.into_iter().map(move |shift| {
+            let mut starts = mask;
+            for i in 1..length {
+                starts &= Self::shift_right(mask, i * shift);
+            }
+            (shift, starts)
+        })
+    }
+
+    fn count_lines_at_least(mask: u64, length: u32) -> u32 {
+        Self::line_starts(mask, length)
+            .map(|(_, starts)| starts.count_ones())
+            .sum()
+    }
+
+    /// Number of lines of exactly `length` cells of the mask, in all directions
+    fn count_lines(mask: u64, length: u32) -> u16 {
+        Self::line_starts(mask, length)
+            .map(|(shift, starts)| {
+                // only the lines that are not preceded or followed by a cell of the same player
+                let starts = starts & !(mask << shift) & !Self::shift_right(mask, length * shift);
+                starts.count_ones() as u16
+            })
+            .sum()
+    }
+
+    /**
+     * Returns the number of lines of `N - 2` and of `N - 1` cells of the player, counted the same
+     * way as `ConnectN`
+     */
+    pub fn alignments(&self, player: NonZeroU8) -> (u16, u16) {
+        let mask = self.masks[Self::player_index(player)];
+        (
+            Self::count_lines(mask, N as u32 - 2),
+            Self::count_lines(mask, N as u32 - 1),
+        )
+    }
+}
+
+impl<const W: usize, const H: usize, const N: usize> Game for BitBoardConnectN<W, H, N> {
+    /// Same as `ConnectN`: (row, column), starting at (0, 0) at the top left corner
+    type Coordinate = (usize, usize);
+
+    /// an usize from 1 to W
+    type InputCoordinate = NonZeroUsize;
+
+    /**
+     * The player is represented by 1 or 2
+     */
+    type Player = NonZeroU8;
+
+    type Score = i32;
+
+    fn new() -> Self {
+        const { assert!(W * (H + 1) <= 64, "the board does not fit in 64 bits") };
+        // same bounds as ConnectN::new
+        const {
+            assert!(
+                N >= 3 && (N <= W || N <= H),
+                "N must be from 3 to the board size"
+            )
+        };
+        BitBoardConnectN {
+            masks: [0; 2],
+            heights: [0; W],
+            winner: None,
+            hash: 0,
+            history: [[0; W]; H],
+            play_count: 0,
+        }
+    }
+
+    fn get(&self, (row, column): (usize, usize)) -> Option<&NonZeroU8> {
+        if row >= H || column >= W {
+            return None;
+        }
+        let bit = Self::bit(row, column);
+        PLAYERS
+            .iter()
+            .zip(self.masks)
+            .find(|(_, mask)| mask & bit != 0)
+            .map(|(player, _)| player)
+    }
+
+    fn play<'a>(&mut self, player: NonZeroU8, column: NonZeroUsize) -> Result<(), &'a str> {
+        let x = column.get() - 1;
+        if x >= W {
+            return Err("Column out of bounds");
+        }
+        let height = self.heights[x] as usize;
+        if height == H {
+            return Err("Column full");
+        }
+        let y = H - 1 - height;
+        let mask = &mut self.masks[Self::player_index(player)];
+        *mask |= Self::bit(y, x);
+        if Self::has_line(*mask) {
+            self.winner = Some(player);
+        }
+        self.heights[x] += 1;
+        self.hash ^= ConnectN::<W, H, N>::zobrist_key(y, x, player);
+        self.set_played_column(self.play_count as usize, x);
+        self.play_count += 1;
+        Ok(())
+    }
+
+    /**
+     * Returns the score of the player, higher is better, see `AlignmentEvaluator`
+     */
+    fn get_score(&self, player: Self::Player) -> Self::Score {
+        AlignmentEvaluator::default().evaluate(self, player)
+    }
+
+    fn get_winner(&self) -> Option<Self::Player> {
+        self.winner
+    }
+
+    fn is_full(&self) -> bool {
+        self.heights.iter().all(|&height| height as usize == H)
+    }
+
+    fn possible_plays(&self) -> Vec<NonZeroUsize> {
+        ConnectN::<W, H, N>::play_order()
+            .iter()
+            .filter(|&column| (self.heights[column - 1] as usize) < H)
+            .map(|&column| NonZeroUsize::new(column).unwrap())
+            .collect()
+    }
+
+    fn shuffle_plays(&self, plays: &mut [NonZeroUsize], random: u64) {
+        ConnectN::<W, H, N>::shuffle_columns(plays, random)
+    }
+
+    fn print(&self) {
+        ConnectN::from(self).print()
+    }
+
+    fn last_play(&self) -> Option<Self::InputCoordinate> {
+        let last_index = (self.play_count as usize).checked_sub(1)?;
+        NonZeroUsize::new(self.played_column(last_index) + 1)
+    }
+
+    fn undo(&mut self) -> Option<Self::InputCoordinate> {
+        let column = self.last_play()?;
+        let x = column.get() - 1;
+        self.heights[x] -= 1;
+        let y = H - 1 - self.heights[x] as usize;
+        let bit = Self::bit(y, x);
+        let player = *self.get((y, x)).unwrap();
+        self.masks[Self::player_index(player)] &= !bit;
+        self.hash ^= ConnectN::<W, H, N>::zobrist_key(y, x, player);
+        self.play_count -= 1;
+        self.set_played_column(self.play_count as usize, 0);
+        self.winner = None;
+        Some(column)
+    }
+
+    fn hash_key(&self) -> u64 {
+        self.hash
+    }
+
+    fn remaining_plays(&self) -> Option<u32> {
+        Some((W * H) as u32 - self.play_count as u32)
+    }
+}
+
+impl<const W: usize, const H: usize, const N: usize> From<&BitBoardConnectN<W, H, N>>
+    for ConnectN<W, H, N>
+{
+    /// Replays the plays of the bitboard game, in the same order
+    fn from(bitboard: &BitBoardConnectN<W, H, N>) -> Self {
+        let mut game = ConnectN::new();
+        let mut heights = [0; W];
+        for index in 0..bitboard.play_count as usize {
+            let x = bitboard.played_column(index);
+            let y = H - 1 - heights[x];
+            heights[x] += 1;
+            let player = *bitboard.get((y, x)).unwrap();
+            game.play_usize(player, x + 1).unwrap();
+        }
+        game
+    }
+}
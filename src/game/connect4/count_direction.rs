@@ -13,32 +13,36 @@ pub enum CountDirection {
 }
 
 impl CountDirection {
-    pub fn add_to(&self, coords: (usize, usize)) -> Option<(usize, usize)> {
+    /// Next cell in this direction on a board of `W` columns and `H` rows, if it is on the board
+    pub fn add_to<const W: usize, const H: usize>(
+        &self,
+        coords: (usize, usize),
+    ) -> Option<(usize, usize)> {
         let (y, x) = coords;
         match self {
             CountDirection::HorizontalRight => {
-                if x < 6 {
+                if x < W - 1 {
                     Some((y, x + 1))
                 } else {
                     None
                 }
             }
             CountDirection::DiagonalDownRight => {
-                if x < 6 && y < 5 {
+                if x < W - 1 && y < H - 1 {
                     Some((y + 1, x + 1))
                 } else {
                     None
                 }
             }
             CountDirection::VerticalDown => {
-                if y < 5 {
+                if y < H - 1 {
                     Some((y + 1, x))
                 } else {
                     None
                 }
             }
             CountDirection::DiagonalDownLeft => {
-                if x > 0 && y < 5 {
+                if x > 0 && y < H - 1 {
                     Some((y + 1, x - 1))
                 } else {
                     None
@@ -66,7 +70,7 @@ impl CountDirection {
                 }
             }
             CountDirection::DiagonalUpRight => {
-                if x < 6 && y > 0 {
+                if x < W - 1 && y > 0 {
                     Some((y - 1, x + 1))
                 } else {
                     None
@@ -0,0 +1,79 @@
+use std::num::NonZeroU8;
+
+use crate::game::connect4::bitboard::BitBoardConnectN;
+use crate::game::connect4::ConnectN;
+use crate::game::evaluator::Evaluator;
+use crate::game::player::Player;
+use crate::game::Game;
+
+/**
+ * The usual ConnectFour heuristic, counting the lines of 2 or 3 cells of a player that can still
+ * be completed to 4.
+ *
+ * Scores:
+ * - 2 aligned: `two_weight * n` (n = number of 2 aligned)
+ * - 3 aligned: `three_weight * n` (n = number of 3 aligned)
+ * - 4 aligned: infinite
+ *
+ * Subtract the same score for the opponent
+ *
+ * In Connect-N games, "2" and "3" are the lines missing 2 and 1 cells to win (`N - 2` and `N - 1`).
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AlignmentEvaluator {
+    pub two_weight: i32,
+    pub three_weight: i32,
+}
+
+impl AlignmentEvaluator {
+    fn calculate_score(&self, aligns2: u16, aligns3: u16) -> i32 {
+        self.two_weight * aligns2 as i32 + self.three_weight * aligns3 as i32
+    }
+}
+
+impl Default for AlignmentEvaluator {
+    fn default() -> Self {
+        Self {
+            two_weight: 10,
+            three_weight: 100,
+        }
+    }
+}
+
+impl<const W: usize, const H: usize, const N: usize> Evaluator<ConnectN<W, H, N>>
+    for AlignmentEvaluator
+{
+    fn evaluate(&self, game: &ConnectN<W, H, N>, player: NonZeroU8) -> i32 {
+        if let Some(winner) = game.winner {
+            return if winner == player {
+                i32::MAX
+            } else {
+                -i32::MAX
+            };
+        }
+        let p1_score = self.calculate_score(game.p1_aligns2, game.p1_aligns3)
+            - self.calculate_score(game.p2_aligns2, game.p2_aligns3);
+        if player.get() == 1u8 {
+            p1_score
+        } else {
+            -p1_score
+        }
+    }
+}
+
+impl<const W: usize, const H: usize, const N: usize> Evaluator<BitBoardConnectN<W, H, N>>
+    for AlignmentEvaluator
+{
+    fn evaluate(&self, game: &BitBoardConnectN<W, H, N>, player: NonZeroU8) -> i32 {
+        if let Some(winner) = game.get_winner() {
+            return if winner == player {
+                i32::MAX
+            } else {
+                -i32::MAX
+            };
+        }
+        let (aligns2, aligns3) = game.alignments(player);
+        let (other_aligns2, other_aligns3) = game.alignments(player.other());
+        self.calculate_score(aligns2, aligns3) - self.calculate_score(other_aligns2, other_aligns3)
+    }
+}
@@ -1,6 +1,6 @@
 use strum_macros::EnumIter;
 
-use crate::game::connect4::ConnectFour;
+use crate::game::connect4::ConnectN;
 use crate::game::Game;
 
 #[derive(EnumIter, Debug, Copy, Clone, PartialEq)]
@@ -11,20 +11,20 @@ pub enum P4IteratorType {
     DiagonalUp,
 }
 
-pub struct BoardIterator<'a> {
-    game: &'a ConnectFour,
+pub struct BoardIterator<'a, const W: usize, const H: usize, const N: usize> {
+    game: &'a ConnectN<W, H, N>,
     pub iterator_type: P4IteratorType,
     pub y: isize,
     pub x: isize,
 }
 
-impl BoardIterator<'_> {
+impl<const W: usize, const H: usize, const N: usize> BoardIterator<'_, W, H, N> {
     pub fn new_at(
-        game: &ConnectFour,
+        game: &ConnectN<W, H, N>,
         iterator_type: P4IteratorType,
         x: isize,
         y: isize,
-    ) -> BoardIterator {
+    ) -> BoardIterator<'_, W, H, N> {
         BoardIterator {
             game,
             iterator_type,
@@ -51,13 +51,13 @@ impl BoardIterator<'_> {
 
     pub fn get_with_offset(&self, offset: isize) -> Option<<Self as Iterator>::Item> {
         let (y, x) = self.get_coords_with_offset(offset);
-        if x < 0 || x >= 7 || y < 0 || y >= 6 {
+        if x < 0 || x >= W as isize || y < 0 || y >= H as isize {
             return None;
         }
         Some(self.game.get_isize((y, x)))
     }
 
-    pub fn to_string(self) -> String {
+    pub fn into_string(self) -> String {
         let mut result = String::new();
         for cell in self {
             result.push(match cell {
@@ -77,22 +77,22 @@ impl BoardIterator<'_> {
     }
 }
 
-impl<'a> Iterator for BoardIterator<'a> {
-    type Item = Option<<ConnectFour as Game>::Player>;
+impl<'a, const W: usize, const H: usize, const N: usize> Iterator for BoardIterator<'a, W, H, N> {
+    type Item = Option<<ConnectN<W, H, N> as Game>::Player>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let (y, x) = self.increment();
-        if self.x < 0 || self.x >= 7 || self.y < 0 || self.y >= 6 {
+        if self.x < 0 || self.x >= W as isize || self.y < 0 || self.y >= H as isize {
             return None;
         }
         Some(self.game.get_isize((y, x)))
     }
 }
 
-impl<'a> IntoIterator for &'a ConnectFour {
-    type Item = <BoardIterator<'a> as Iterator>::Item;
+impl<'a, const W: usize, const H: usize, const N: usize> IntoIterator for &'a ConnectN<W, H, N> {
+    type Item = <BoardIterator<'a, W, H, N> as Iterator>::Item;
 
-    type IntoIter = BoardIterator<'a>;
+    type IntoIter = BoardIterator<'a, W, H, N>;
 
     fn into_iter(self) -> Self::IntoIter {
         BoardIterator {
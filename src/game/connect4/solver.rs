@@ -0,0 +1,404 @@
+use std::num::NonZeroUsize;
+
+use crate::game::connect4::ConnectFour;
+use crate::game::Game;
+use crate::min_max::outcome::Solution;
+
+const WIDTH: usize = ConnectFour::WIDTH;
+const HEIGHT: usize = ConnectFour::HEIGHT;
+const CELLS: i32 = (WIDTH * HEIGHT) as i32;
+
+/// Bit of the bottom cell of each column
+const BOTTOM_MASK: u64 = {
+    let mut mask = 0;
+    let mut column = 0;
+    while column < WIDTH {
+        mask |= 1 << (column * (HEIGHT + 1));
+        column += 1;
+    }
+    mask
+};
+
+/// Bits of all the cells of the board, without the extra bit on top of each column
+const BOARD_MASK: u64 = BOTTOM_MASK * ((1 << HEIGHT) - 1);
+
+/// Bits of all the cells of the first column, the extra bit included
+const COLUMN_MASK: u64 = (1 << (HEIGHT + 1)) - 1;
+
+fn column_mask(column: usize) -> u64 {
+    COLUMN_MASK << (column * (HEIGHT + 1))
+}
+
+fn top_mask(column: usize) -> u64 {
+    1 << (HEIGHT - 1 + column * (HEIGHT + 1))
+}
+
+/**
+ * A Connect Four position for the `Solver`, stored from the point of view of the player who
+ * plays next, with the same bits as `BitBoardConnectN`: bit `(HEIGHT + 1) * column + height` is
+ * the cell at `height` from the bottom of `column`, and the extra bit on top of each column is
+ * always empty.
+ */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Position {
+    /// Cells of the player who plays next
+    current: u64,
+    /// Cells of both players
+    mask: u64,
+    /// Number of plays made since the start
+    moves: u32,
+}
+
+impl Position {
+    /// The position after the columns (from '1' to '7') of `columns` were played from the start,
+    /// like "4435". Fails if a column is invalid or full, or if a play wins the game.
+    pub fn from_columns(columns: &str) -> Result<Self, &'static str> {
+        let mut position = Position {
+            current: 0,
+            mask: 0,
+            moves: 0,
+        };
+        for column in columns.chars() {
+            let column = match column.to_digit(10) {
+                Some(column @ 1..) if column as usize <= WIDTH => column as usize - 1,
+                _ => return Err("Invalid column"),
+            };
+            if !position.can_play(column) {
+                return Err("Column full");
+            }
+            if position.is_winning_play(column) {
+                return Err("The game is already won");
+            }
+            position.play(column);
+        }
+        Ok(position)
+    }
+
+    /// Number of plays made since the start
+    pub fn moves(&self) -> u32 {
+        self.moves
+    }
+
+    fn can_play(&self, column: usize) -> bool {
+        self.mask & top_mask(column) == 0
+    }
+
+    /// Plays in `column` for the player who plays next, who then becomes the other one
+    fn play(&mut self, column: usize) {
+        self.play_bit((self.mask + BOTTOM_MASK) & column_mask(column));
+    }
+
+    /// Plays the cell of `bit`, which must be playable
+    fn play_bit(&mut self, bit: u64) {
+        self.current ^= self.mask;
+        self.mask |= bit;
+        self.moves += 1;
+    }
+
+    fn is_winning_play(&self, column: usize) -> bool {
+        self.winning_cells() & self.possible() & column_mask(column) != 0
+    }
+
+    fn can_win_next(&self) -> bool {
+        self.winning_cells() & self.possible() != 0
+    }
+
+    /// The cells where a play can be made
+    fn possible(&self) -> u64 {
+        (self.mask + BOTTOM_MASK) & BOARD_MASK
+    }
+
+    /// The empty cells where the player who plays next would win
+    fn winning_cells(&self) -> u64 {
+        winning_cells(self.current, self.mask)
+    }
+
+    /// The empty cells where the other player would win
+    fn opponent_winning_cells(&self) -> u64 {
+        winning_cells(self.current ^ self.mask, self.mask)
+    }
+
+    /// The playable cells that do not let the other player win right after, assuming that the
+    /// player who plays next cannot win right away. 0 if every play loses.
+    fn non_losing_plays(&self) -> u64 {
+        let mut possible = self.possible();
+        let opponent_wins = self.opponent_winning_cells();
+        let forced = possible & opponent_wins;
+        if forced != 0 {
+            if forced & (forced - 1) != 0 {
+                // two threats cannot both be blocked
+                return 0;
+            }
+            possible = forced;
+        }
+        // playing below a winning cell of the other player makes it playable
+        possible & !(opponent_wins >> 1)
+    }
+
+    /// How many winning cells the player who plays next would have after playing `bit`, to
+    /// explore the most threatening plays first
+    fn play_score(&self, bit: u64) -> u32 {
+        winning_cells(self.current | bit, self.mask).count_ones()
+    }
+
+    /// Unique key of the position, the same for a position and its mirror image, which have the
+    /// same score
+    fn key(&self) -> u64 {
+        let key = self.current + self.mask;
+        key.min(mirror(key))
+    }
+}
+
+impl From<&ConnectFour> for Position {
+    /// The position of `game`, which must not be won yet, where the player who did not make the
+    /// last play plays next
+    fn from(game: &ConnectFour) -> Self {
+        // the solver only looks for the alignments of the next plays
+        assert!(game.get_winner().is_none(), "the game is already won");
+        let mut cells = [0u64; 2];
+        let mut moves = 0;
+        for column in 0..WIDTH {
+            for height in 0..HEIGHT {
+                if let Some(player) = game.get((HEIGHT - 1 - height, column)) {
+                    cells[player.get() as usize - 1] |= 1 << (column * (HEIGHT + 1) + height);
+                    moves += 1;
+                }
+            }
+        }
+        let last_player = game
+            .last_played_coords
+            .and_then(|coords| game.get(coords))
+            .map_or(2, |player| player.get());
+        Position {
+            current: cells[last_player as usize % 2],
+            mask: cells[0] | cells[1],
+            moves,
+        }
+    }
+}
+
+/// The empty cells that would complete a line of 4 with the cells of `position`
+fn winning_cells(position: u64, mask: u64) -> u64 {
+    // vertical
+    let mut cells = (position << 1) & (position << 2) & (position << 3);
+    // horizontal, then both diagonals: the cell can be at any place of the line
+    for shift in [HEIGHT + 1, HEIGHT, HEIGHT + 2] {
+        let pair = (position << shift) & (position << (2 * shift));
+        cells |= pair & (position << (3 * shift));
+        cells |= pair & (position >> shift);
+        let pair = (position >> shift) & (position >> (2 * shift));
+        cells |= pair & (position << shift);
+        cells |= pair & (position >> (3 * shift));
+    }
+    cells & (BOARD_MASK ^ mask)
+}
+
+/// The same bits with the columns in reverse order
+fn mirror(bits: u64) -> u64 {
+    (0..WIDTH).fold(0, |mirrored, column| {
+        let column_bits = (bits >> (column * (HEIGHT + 1))) & COLUMN_MASK;
+        mirrored | column_bits << ((WIDTH - 1 - column) * (HEIGHT + 1))
+    })
+}
+
+/// The solution of a position after `moves` plays for the player who plays next, whose score is
+/// `score`, see `Solver::score`
+fn solution(score: i32, moves: u32) -> Solution {
+    let moves = moves as i32;
+    if score > 0 {
+        // the score of a win at the next play is (CELLS + 1 - moves) / 2, and each play of the
+        // winner before it lowers it by 1
+        let winner_plays = (CELLS + 1 - moves) / 2 - score + 1;
+        Solution::Win(2 * winner_plays as u32 - 1)
+    } else if score < 0 {
+        let winner_plays = (CELLS - moves) / 2 + score + 1;
+        Solution::Loss(2 * winner_plays as u32)
+    } else {
+        Solution::Draw
+    }
+}
+
+/// Transposition table of the solver: each entry is the key of a position and an upper bound of
+/// its score, shifted to be at least 1 so that 0 is an empty entry (the key of the empty board is
+/// 0 too)
+struct Table {
+    keys: Vec<u64>,
+    values: Vec<i8>,
+}
+
+impl Table {
+    fn new(entries: usize) -> Self {
+        Self {
+            keys: vec![0; entries],
+            values: vec![0; entries],
+        }
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.keys.len() as u64) as usize
+    }
+
+    fn store(&mut self, key: u64, upper_bound: i32) {
+        let index = self.index(key);
+        self.keys[index] = key;
+        self.values[index] = (upper_bound + CELLS / 2 + 1) as i8;
+    }
+
+    fn upper_bound(&self, key: u64) -> Option<i32> {
+        let index = self.index(key);
+        (self.keys[index] == key && self.values[index] != 0)
+            .then(|| self.values[index] as i32 - CELLS / 2 - 1)
+    }
+}
+
+/**
+ * Exact Connect Four solver: searches until the end of the game, with no heuristic, to find who
+ * wins if both players play perfectly, and how fast.
+ *
+ * The score of a position (see `score`) is searched with null windows, which only tell whether it
+ * is above a value, and a binary search on this value. The positions are remembered in a
+ * transposition table, where a position and its mirror image are the same entry. The plays that
+ * let the other player win right after are never explored, and the others are explored from the
+ * one making the most threats.
+ */
+pub struct Solver {
+    table: Table,
+    nodes: u64,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new(1 << 23)
+    }
+}
+
+impl Solver {
+    /// Creates a solver whose table has `entries` entries, of 9 bytes each
+    pub fn new(entries: usize) -> Self {
+        Self {
+            table: Table::new(entries.max(1)),
+            nodes: 0,
+        }
+    }
+
+    /// Number of positions explored since the solver was created
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// The result of `position` for the player who plays next, see `Solution`
+    pub fn solve(&mut self, position: &Position) -> Solution {
+        solution(self.score(position), position.moves)
+    }
+
+    /**
+     * The score of `position` for the player who plays next, as in the usual Connect Four
+     * benchmarks: 0 for a draw, the number of their own cells left empty when they win, counting
+     * the winning one, and minus the number of cells left empty to the other player when they
+     * lose. The faster the win, the higher the score.
+     */
+    pub fn score(&mut self, position: &Position) -> i32 {
+        if position.can_win_next() {
+            return (CELLS + 1 - position.moves as i32) / 2;
+        }
+        let mut min = -(CELLS - position.moves as i32) / 2;
+        let mut max = (CELLS + 1 - position.moves as i32) / 2;
+        while min < max {
+            // tries values closer to 0 first, as they are faster to refute
+            let mut value = min + (max - min) / 2;
+            if value <= 0 && min / 2 < value {
+                value = min / 2;
+            } else if value >= 0 && max / 2 > value {
+                value = max / 2;
+            }
+            let score = self.negamax(position, value, value + 1);
+            if score <= value {
+                max = score;
+            } else {
+                min = score;
+            }
+        }
+        min
+    }
+
+    /// The solution of each possible play of `position` for the player making it, from the best
+    /// to the worst. The plays as good as each other are in the order of `Game::possible_plays`.
+    pub fn analyze(&mut self, position: &Position) -> Vec<(NonZeroUsize, Solution)> {
+        let mut plays: Vec<(NonZeroUsize, i32)> = ConnectFour::play_order()
+            .into_iter()
+            .filter(|&column| position.can_play(column - 1))
+            .map(|column| {
+                let score = if position.is_winning_play(column - 1) {
+                    (CELLS + 1 - position.moves as i32) / 2
+                } else {
+                    let mut child = *position;
+                    child.play(column - 1);
+                    -self.score(&child)
+                };
+                (NonZeroUsize::new(column).unwrap(), score)
+            })
+            .collect();
+        plays.sort_by_key(|(_, score)| -score);
+        plays
+            .into_iter()
+            .map(|(play, score)| (play, solution(score, position.moves)))
+            .collect()
+    }
+
+    /// Fail-soft alpha-beta search of the score of `position`, which cannot be won by the next
+    /// play: the exact score is returned if it is strictly between `alpha` and `beta`, otherwise a
+    /// bound of it
+    fn negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        let plays = position.non_losing_plays();
+        let moves = position.moves as i32;
+        if plays == 0 {
+            // the other player wins at their next play
+            return -(CELLS - moves) / 2;
+        }
+        if moves >= CELLS - 2 {
+            // nobody can win with the last two cells
+            return 0;
+        }
+
+        // the other player cannot win at their next play
+        let min = -(CELLS - 2 - moves) / 2;
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta {
+                return alpha;
+            }
+        }
+        // this player cannot win at their next play either
+        let max = self
+            .table
+            .upper_bound(position.key())
+            .unwrap_or((CELLS - 1 - moves) / 2);
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        let mut sorted: Vec<(u32, u64)> = ConnectFour::play_order()
+            .into_iter()
+            .map(|column| plays & column_mask(column - 1))
+            .filter(|&bit| bit != 0)
+            .map(|bit| (position.play_score(bit), bit))
+            .collect();
+        // stable, so that the plays making as many threats stay closest to the center first
+        sorted.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        for (_, bit) in sorted {
+            let mut child = *position;
+            child.play_bit(bit);
+            let score = -self.negamax(&child, -beta, -alpha);
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        self.table.store(position.key(), alpha);
+        alpha
+    }
+}
@@ -1,21 +1,35 @@
 #[cfg(test)]
 mod p4_tests {
+    use std::collections::HashMap;
     use std::num::{NonZeroU8, NonZeroUsize};
 
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
     use strum::IntoEnumIterator;
 
+    use crate::game::connect4::bitboard::{BitBoardConnectFour, BitBoardConnectN};
     use crate::game::connect4::count_direction::CountDirection;
+    use crate::game::connect4::evaluator::AlignmentEvaluator;
     use crate::game::connect4::iteration::P4IteratorType;
-    use crate::game::connect4::ConnectFour;
+    use crate::game::connect4::solver::{Position, Solver};
+    use crate::game::connect4::{ConnectFour, ConnectN};
+    use crate::game::evaluator::{Evaluator, GameScore};
+    use crate::game::player::Player;
     use crate::game::Game;
+    use crate::min_max::cancellation::CancellationToken;
+    use crate::min_max::config::{DepthPenalty, SearchConfig};
+    use crate::min_max::make_unmake;
     use crate::min_max::node::GameNode;
+    use crate::min_max::outcome::{Outcome, Solution};
+    use crate::min_max::transposition::TranspositionTable;
 
     #[test]
     fn lines_passing_at() {
         let power4 = ConnectFour::new();
-        let lines = power4.lines_passing_at_longer_4((1, 1));
+        let lines = power4.lines_passing_at_longer_n((1, 1));
         assert_eq!(lines.len(), 3);
-        let lines = power4.lines_passing_at_longer_4((2, 3));
+        let lines = power4.lines_passing_at_longer_n((2, 3));
         assert_eq!(
             lines
                 .iter()
@@ -24,14 +38,14 @@ mod p4_tests {
             vec![(2, 0, P4IteratorType::Horizontal), (0, 3, P4IteratorType::Vertical), (0, 1, P4IteratorType::DiagonalDown), (5, 0, P4IteratorType::DiagonalUp)]
         );
         assert_eq!(
-            power4.lines_passing_at_longer_4((3, 2))
+            power4.lines_passing_at_longer_n((3, 2))
                 .iter()
                 .map(|iter| (iter.y, iter.x, iter.iterator_type))
                 .collect::<Vec<_>>(),
             vec![(3, 0, P4IteratorType::Horizontal), (0, 2, P4IteratorType::Vertical), (1, 0, P4IteratorType::DiagonalDown), (5, 0, P4IteratorType::DiagonalUp)]
         );
         let lines = power4
-            .lines_passing_at_longer_4((3, 4))
+            .lines_passing_at_longer_n((3, 4))
             .iter()
             .map(|iter| iter.iterator_type)
             .collect::<Vec<_>>();
@@ -96,7 +110,15 @@ mod p4_tests {
 
         println!();
         let mut game_tree = GameNode::new_root(power4.clone(), p2, 0);
-        game_tree.explore_children(p2, 2, 0);
+        game_tree.explore_children(
+            p2,
+            2,
+            &SearchConfig::default(),
+            &GameScore,
+            &TranspositionTable::default(),
+            None,
+            &CancellationToken::new(),
+        );
         let wrong_play = NonZeroUsize::new(4).unwrap();
         let wrong_chosen_node = &game_tree
             .children()
@@ -144,24 +166,18 @@ mod p4_tests {
         power4.print();
 
         assert_eq!(power4.last_played_coords.unwrap(), (2, 0));
-        assert_eq!(power4.lines_passing_at_longer_4((2, 0)).len(), 3);
+        assert_eq!(power4.lines_passing_at_longer_n((2, 0)).len(), 3);
 
         assert_eq!(power4.get_winner(), Some(p1));
 
         power4.print();
         println!();
-        // let mut game_tree = GameNode::new_root(connect4.clone(), p2, 0);
-        // game_tree.explore_children(p2, 2, 0);
-        // println!("Tree:\n {}", game_tree.debug(3));
-        // let wrong_chosen_node = game_tree.children().get(&0usize).unwrap();
-        // assert_ne!(wrong_chosen_node.weight().unwrap(), 0);
     }
 
     #[test]
     fn count_in_direction() {
         let mut power4 = ConnectFour::new();
         let p1 = NonZeroU8::new(1).unwrap();
-        let p2 = NonZeroU8::new(2).unwrap();
 
         power4.play_usize(p1, 1).unwrap();
         power4.play_usize(p1, 2).unwrap();
@@ -214,4 +230,492 @@ mod p4_tests {
         power4.print();
         assert_eq!(power4.get_score(p1), 100); // there is still enough space to win
     }
+
+    #[test]
+    fn hash_key_transpositions() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+
+        let mut a = ConnectFour::new();
+        a.play_usize(p1, 4).unwrap();
+        a.play_usize(p2, 4).unwrap();
+        a.play_usize(p1, 3).unwrap();
+        a.play_usize(p2, 3).unwrap();
+
+        let mut b = ConnectFour::new();
+        b.play_usize(p1, 3).unwrap();
+        b.play_usize(p2, 3).unwrap();
+        b.play_usize(p1, 4).unwrap();
+        b.play_usize(p2, 4).unwrap();
+
+        assert_ne!(a.hash_key(), ConnectFour::new().hash_key());
+        assert_eq!(a.board, b.board);
+        assert_eq!(a.hash_key(), b.hash_key());
+
+        // same columns but other players
+        let mut c = ConnectFour::new();
+        c.play_usize(p2, 4).unwrap();
+        c.play_usize(p1, 4).unwrap();
+        c.play_usize(p2, 3).unwrap();
+        c.play_usize(p1, 3).unwrap();
+        assert_ne!(a.hash_key(), c.hash_key());
+    }
+
+    #[test]
+    fn hash_key_random_games() {
+        let mut rng = rand::thread_rng();
+        let mut boards_by_key = HashMap::new();
+
+        for _ in 0..2000 {
+            let mut power4 = ConnectFour::new();
+            let mut player = NonZeroU8::new(1).unwrap();
+            while power4.get_winner().is_none() && !power4.is_full() {
+                let column = *power4.possible_plays().choose(&mut rng).unwrap();
+                power4.play(player, column).unwrap();
+                player = player.other();
+
+                let mut from_scratch = 0;
+                for y in 0..6 {
+                    for x in 0..7 {
+                        if let Some(cell_player) = power4.board[y][x] {
+                            from_scratch ^= ConnectFour::zobrist_key(y, x, cell_player);
+                        }
+                    }
+                }
+                assert_eq!(power4.hash_key(), from_scratch);
+
+                let board = boards_by_key
+                    .entry(power4.hash_key())
+                    .or_insert(power4.board);
+                assert_eq!(
+                    *board, power4.board,
+                    "two different boards have the same key"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn alignment_evaluator_weights() {
+        let mut power4 = ConnectFour::new();
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+
+        power4.play_usize(p1, 2).unwrap();
+        power4.play_usize(p1, 3).unwrap();
+        power4.play_usize(p1, 4).unwrap();
+        power4.play_usize(p2, 4).unwrap();
+        power4.play_usize(p2, 5).unwrap();
+
+        let default = AlignmentEvaluator::default();
+        assert_eq!(default.evaluate(&power4, p1), power4.get_score(p1));
+        assert_eq!(default.evaluate(&power4, p2), power4.get_score(p2));
+
+        let only_threes = AlignmentEvaluator {
+            two_weight: 0,
+            three_weight: 1,
+        };
+        assert_eq!(only_threes.evaluate(&power4, p1), 1);
+        assert_eq!(only_threes.evaluate(&power4, p2), -1);
+
+        power4.play_usize(p1, 5).unwrap();
+        power4.play_usize(p1, 1).unwrap();
+        assert_eq!(only_threes.evaluate(&power4, p1), i32::MAX);
+        assert_eq!(only_threes.evaluate(&power4, p2), -i32::MAX);
+    }
+
+    #[test]
+    fn undo_restores_game() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..500 {
+            let mut power4 = ConnectFour::new();
+            let mut player = NonZeroU8::new(1).unwrap();
+            let mut states = vec![power4.clone()];
+            let mut plays = Vec::new();
+            while power4.get_winner().is_none() && !power4.is_full() {
+                let column = *power4.possible_plays().choose(&mut rng).unwrap();
+                power4.play(player, column).unwrap();
+                assert_eq!(power4.last_play(), Some(column));
+                player = player.other();
+                states.push(power4.clone());
+                plays.push(column);
+            }
+            states.pop();
+            while let Some(state) = states.pop() {
+                assert_eq!(power4.undo(), plays.pop());
+                assert_eq!(power4, state);
+            }
+            assert_eq!(power4.undo(), None);
+        }
+    }
+
+    #[test]
+    fn score_does_not_depend_on_play_order() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+
+        let mut sides_first = ConnectFour::new();
+        for column in [2, 4, 3] {
+            sides_first.play_usize(p1, column).unwrap();
+        }
+        let mut in_order = ConnectFour::new();
+        for column in [2, 3, 4] {
+            in_order.play_usize(p1, column).unwrap();
+        }
+        assert_eq!(sides_first.get_score(p1), 100);
+        assert_eq!(in_order.get_score(p1), 100);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..500 {
+            let mut power4 = ConnectFour::new();
+            let mut plays = Vec::new();
+            let mut player = p1;
+            let length = rng.gen_range(0..=ConnectFour::WIDTH * ConnectFour::HEIGHT);
+            while power4.get_winner().is_none() && !power4.is_full() && plays.len() < length {
+                let column = *power4.possible_plays().choose(&mut rng).unwrap();
+                power4.play(player, column).unwrap();
+                plays.push((player, column));
+                player = player.other();
+            }
+            // in another order, the winning line could be completed before the end
+            if power4.get_winner().is_some() {
+                continue;
+            }
+            // replaying the same plays column by column gives the same board
+            plays.sort_by_key(|(_, column)| *column);
+            let mut replayed = ConnectFour::new();
+            for (player, column) in plays {
+                replayed.play(player, column).unwrap();
+            }
+            assert_eq!(replayed.board, power4.board);
+            assert_eq!(replayed.get_score(p1), power4.get_score(p1));
+            assert_eq!(replayed.get_score(p2), power4.get_score(p2));
+        }
+    }
+
+    fn assert_same_game<const W: usize, const H: usize, const N: usize>(
+        bitboard: &BitBoardConnectN<W, H, N>,
+        power4: &ConnectN<W, H, N>,
+    ) {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+        for y in 0..H {
+            for x in 0..W {
+                assert_eq!(bitboard.get((y, x)), power4.get((y, x)));
+            }
+        }
+        assert_eq!(bitboard.get_winner(), power4.get_winner());
+        assert_eq!(bitboard.is_full(), power4.is_full());
+        assert_eq!(bitboard.last_play(), power4.last_play());
+        assert_eq!(bitboard.hash_key(), power4.hash_key());
+        assert_eq!(bitboard.get_score(p1), power4.get_score(p1));
+        assert_eq!(bitboard.get_score(p2), power4.get_score(p2));
+        let mut bitboard_plays = bitboard.possible_plays();
+        let mut power4_plays = power4.possible_plays();
+        bitboard_plays.sort();
+        power4_plays.sort();
+        assert_eq!(bitboard_plays, power4_plays);
+    }
+
+    fn cross_check_random_games<const W: usize, const H: usize, const N: usize>(games: usize) {
+        let mut rng = rand::thread_rng();
+        for _ in 0..games {
+            let mut bitboard = BitBoardConnectN::<W, H, N>::new();
+            let mut power4 = ConnectN::<W, H, N>::new();
+            let mut player = NonZeroU8::new(1).unwrap();
+            while power4.get_winner().is_none() && !power4.is_full() {
+                let column = *power4.possible_plays().choose(&mut rng).unwrap();
+                bitboard.play(player, column).unwrap();
+                power4.play(player, column).unwrap();
+                assert_same_game(&bitboard, &power4);
+                player = player.other();
+            }
+            assert_eq!(ConnectN::from(&bitboard), power4);
+            while power4.last_play().is_some() {
+                assert_eq!(bitboard.undo(), power4.undo());
+                assert_same_game(&bitboard, &power4);
+            }
+            assert_eq!(bitboard, BitBoardConnectN::new());
+        }
+    }
+
+    #[test]
+    fn bitboard_matches_connect_four() {
+        cross_check_random_games::<7, 6, 4>(1000);
+    }
+
+    #[test]
+    fn bitboard_matches_other_sizes() {
+        cross_check_random_games::<8, 7, 4>(300);
+        cross_check_random_games::<6, 5, 4>(300);
+        cross_check_random_games::<7, 7, 5>(300);
+        cross_check_random_games::<5, 4, 3>(300);
+    }
+
+    #[test]
+    fn shuffled_plays_stay_center_first() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+        let mut game = ConnectFour::new();
+        let mut bitboard = BitBoardConnectFour::new();
+        // fills the first column without winning
+        for player in [p1, p1, p2, p2, p1, p1] {
+            game.play_usize(player, 1).unwrap();
+            bitboard
+                .play(player, NonZeroUsize::new(1).unwrap())
+                .unwrap();
+        }
+        let plays = game.possible_plays();
+        let distance = |play: &NonZeroUsize| (2 * play.get()).abs_diff(ConnectFour::WIDTH + 1);
+        let mut orders = HashMap::new();
+        for random in 0..64 {
+            let mut shuffled = plays.clone();
+            game.shuffle_plays(&mut shuffled, random);
+            assert_eq!(
+                shuffled.iter().map(distance).collect::<Vec<_>>(),
+                plays.iter().map(distance).collect::<Vec<_>>()
+            );
+            let mut again = plays.clone();
+            game.shuffle_plays(&mut again, random);
+            assert_eq!(shuffled, again);
+
+            // the bitboard orders the plays the same way
+            let mut bitboard_shuffled = bitboard.possible_plays();
+            bitboard.shuffle_plays(&mut bitboard_shuffled, random);
+            assert_eq!(shuffled, bitboard_shuffled);
+            orders.insert(shuffled, random);
+        }
+        // 3 and 5 can be swapped, 2 and 6 too, but 1 is full
+        assert_eq!(orders.len(), 4);
+    }
+
+    #[test]
+    fn connect_five() {
+        let mut game = ConnectN::<9, 7, 5>::new();
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+
+        for column in 1..=9 {
+            assert!(game
+                .possible_plays()
+                .contains(&NonZeroUsize::new(column).unwrap()));
+        }
+        for player in [p2, p2, p1, p1, p2, p2, p1] {
+            game.play_usize(player, 9).unwrap();
+        }
+        assert!(game.play_usize(p2, 9).is_err());
+        assert!(game.play_usize(p2, 10).is_err());
+        assert_eq!(game.possible_plays().len(), 8);
+
+        for column in 1..=4 {
+            game.play_usize(p1, column).unwrap();
+        }
+        assert_eq!(game.get_winner(), None);
+        // 4 aligned is only one cell from winning
+        assert_eq!(game.get_score(p1), 100);
+
+        game.play_usize(p1, 5).unwrap();
+        game.print();
+        assert_eq!(game.get_winner(), Some(p1));
+        assert_eq!(
+            game.get_winner_coords(),
+            Some([(6, 0), (6, 1), (6, 2), (6, 3), (6, 4)])
+        );
+    }
+
+    #[test]
+    fn connect_n_search() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        // small enough to be explored until the end
+        let mut game_tree = GameNode::new_root(ConnectN::<4, 3, 3>::new(), p1, 0);
+        assert!(
+            game_tree
+                .explore_children(
+                    p1,
+                    12,
+                    &SearchConfig::default(),
+                    &GameScore,
+                    &TranspositionTable::default(),
+                    None,
+                    &CancellationToken::new(),
+                )
+                .completed
+        );
+        // the first player wins by playing in the middle, at their 5th play
+        assert_eq!(game_tree.weight(), Some(Outcome::Win(9).encode()));
+    }
+
+    #[test]
+    fn bitboard_same_search_weight() {
+        let mut rng = rand::thread_rng();
+        let p1 = NonZeroU8::new(1).unwrap();
+        for _ in 0..10 {
+            let mut bitboard = BitBoardConnectFour::new();
+            let mut power4 = ConnectFour::new();
+            let mut player = p1;
+            for _ in 0..8 {
+                let column = *power4.possible_plays().choose(&mut rng).unwrap();
+                bitboard.play(player, column).unwrap();
+                power4.play(player, column).unwrap();
+                player = player.other();
+            }
+            if power4.get_winner().is_some() {
+                continue;
+            }
+
+            let mut bitboard_tree = GameNode::new_root(bitboard, player, 0);
+            let mut power4_tree = GameNode::new_root(power4, player, 0);
+            let table = TranspositionTable::default();
+            let cancellation = CancellationToken::new();
+            let stats = bitboard_tree.explore_children(
+                player,
+                5,
+                &SearchConfig::default(),
+                &GameScore,
+                &table,
+                None,
+                &cancellation,
+            );
+            assert!(stats.completed);
+            let table = TranspositionTable::default();
+            let stats = power4_tree.explore_children(
+                player,
+                5,
+                &SearchConfig::default(),
+                &GameScore,
+                &table,
+                None,
+                &cancellation,
+            );
+            assert!(stats.completed);
+            assert_eq!(bitboard_tree.weight(), power4_tree.weight());
+        }
+    }
+
+    /// The positions of `solver_positions.txt` with their score
+    fn solver_positions() -> Vec<(&'static str, i32)> {
+        include_str!("solver_positions.txt")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (columns, score) = line.split_once(' ').unwrap();
+                (columns, score.parse().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn solver_scores() {
+        let mut solver = Solver::default();
+        for (columns, score) in solver_positions() {
+            let position = Position::from_columns(columns).unwrap();
+            assert_eq!(solver.score(&position), score, "plays {columns}");
+            // the mirror image has the same score
+            let mirrored: String = columns
+                .chars()
+                .map(|column| char::from(b'8' - column as u8 + b'0'))
+                .collect();
+            let position = Position::from_columns(&mirrored).unwrap();
+            assert_eq!(solver.score(&position), score, "plays {mirrored}");
+        }
+    }
+
+    #[test]
+    fn solver_matches_min_max() {
+        // the last positions, that min-max searches to the end fast enough
+        check_solver_with_min_max(24);
+    }
+
+    /// Checks every position of `solver_positions.txt`, which takes minutes even in release:
+    /// `cargo test --release -- --ignored`
+    #[test]
+    #[ignore = "takes minutes"]
+    fn solver_matches_min_max_everywhere() {
+        check_solver_with_min_max(0);
+    }
+
+    /// Checks that the solver finds the same results as a min-max search to the end of the game
+    /// for the positions of `solver_positions.txt` with at least `min_plays` plays, whose scores
+    /// are the ones of the solver (see `solver_scores`)
+    fn check_solver_with_min_max(min_plays: usize) {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let config = SearchConfig::default()
+            .with_game_score(false)
+            .with_contempt(0)
+            .with_depth_penalty(DepthPenalty::None);
+        let mut solver = Solver::default();
+        for (columns, _) in solver_positions()
+            .into_iter()
+            .filter(|(columns, _)| columns.len() >= min_plays)
+        {
+            let mut game = ConnectFour::new();
+            let mut player = p1;
+            for column in columns.chars() {
+                let column = column.to_digit(10).unwrap() as usize;
+                game.play(player, NonZeroUsize::new(column).unwrap())
+                    .unwrap();
+                player = player.other();
+            }
+            let depth = (ConnectFour::WIDTH * ConnectFour::HEIGHT - columns.len()) as u32;
+            let (variation, _) = make_unmake::best_play(
+                &game,
+                player,
+                depth,
+                &config,
+                &GameScore,
+                &TranspositionTable::default(),
+                None,
+                &CancellationToken::new(),
+            );
+            let expected = Outcome::decode(variation.unwrap()[0].1).solution();
+            let position = Position::from(&game);
+            assert_eq!(position, Position::from_columns(columns).unwrap());
+            assert_eq!(solver.solve(&position), expected, "plays {columns}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the game is already won")]
+    fn no_position_of_won_game() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut game = ConnectFour::new();
+        for column in [1, 2, 3, 4] {
+            game.play_usize(p1, column).unwrap();
+        }
+        let _ = Position::from(&game);
+    }
+
+    #[test]
+    fn solver_analysis() {
+        let mut solver = Solver::default();
+        // player 1 aligned 1, 2 and 3 at the bottom
+        let position = Position::from_columns("152637").unwrap();
+        assert_eq!(solver.solve(&position), Solution::Win(1));
+
+        for (columns, _) in solver_positions()
+            .into_iter()
+            .filter(|(columns, _)| columns.len() >= 24)
+        {
+            let position = Position::from_columns(columns).unwrap();
+            let analysis = solver.analyze(&position);
+            assert_eq!(analysis[0].1, solver.solve(&position), "plays {columns}");
+            for (play, solution) in analysis {
+                // the solution of a play is the one of the position after it, one play earlier
+                let expected = match Position::from_columns(&format!("{columns}{play}")) {
+                    Ok(child) => match solver.solve(&child) {
+                        Solution::Win(plies) => Solution::Loss(plies + 1),
+                        Solution::Loss(plies) => Solution::Win(plies + 1),
+                        Solution::Draw => Solution::Draw,
+                    },
+                    Err(_) => Solution::Win(1),
+                };
+                assert_eq!(solution, expected, "plays {columns}{play}");
+            }
+        }
+
+        assert!(Position::from_columns("1526374").is_err());
+        assert!(Position::from_columns("1111111").is_err());
+        assert!(Position::from_columns("8").is_err());
+        assert!(Position::from_columns("0").is_err());
+    }
 }
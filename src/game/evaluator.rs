@@ -0,0 +1,22 @@
+use crate::game::Game;
+
+/// Heuristic used by the search to score the positions where it stops exploring
+pub trait Evaluator<G: Game>: Send + Sync {
+    /**
+     * Returns the score of the game for `player`, higher is better. Should return
+     * `Score::MAX()` if `player` won and `Score::MIN()` if they lost. The search scores the won and
+     * lost positions itself, and brings the other scores back within `MATE_RANGE` of `MAX` and
+     * `MIN`, see `Outcome`.
+     */
+    fn evaluate(&self, game: &G, player: G::Player) -> G::Score;
+}
+
+/// Uses the score given by the game itself
+#[derive(Debug, Default, Copy, Clone)]
+pub struct GameScore;
+
+impl<G: Game> Evaluator<G> for GameScore {
+    fn evaluate(&self, game: &G, player: G::Player) -> G::Score {
+        game.get_score(player)
+    }
+}
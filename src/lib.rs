@@ -0,0 +1,37 @@
+//! Min-max search (with alpha-beta pruning and multithreading) for two player games, with a
+//! Connect Four implementation. Games without a good heuristic can be played by Monte Carlo tree
+//! search instead, see `MctsBot`.
+//!
+//! Implement `Game` for your game, then let a `Bot` play it:
+//!
+//! ```
+//! use min_max::{Bot, ConnectFour, Game};
+//! use std::num::{NonZeroU8, NonZeroUsize};
+//!
+//! // the bot is player 2 and explores 5 plays ahead
+//! let mut bot: Bot<ConnectFour> = Bot::new(NonZeroU8::new(2).unwrap(), 5);
+//! bot.other_played(NonZeroUsize::new(4).unwrap()).unwrap();
+//! let play = bot.play();
+//! assert_eq!(bot.expect_game().last_play(), Some(play));
+//! ```
+//!
+//! The library never prints anything by itself: the bot reports what it is doing through the
+//! `log` crate, so install a logger to see it.
+
+pub mod bot;
+pub mod game;
+pub mod mcts;
+pub mod min_max;
+pub mod scalar;
+
+pub use bot::{Bot, SearchLimit};
+pub use game::connect4::ConnectFour;
+pub use game::player::Player;
+pub use game::state::GameState;
+pub use game::Game;
+pub use mcts::MctsBot;
+pub use min_max::node::GameNode;
+pub use min_max::outcome::{Outcome, Solution};
+pub use min_max::stats::SearchStats;
+pub use min_max::Variation;
+pub use scalar::Scalar;
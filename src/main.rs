@@ -1,95 +1,424 @@
 use std::io;
 use std::num::{NonZeroU8, NonZeroUsize};
+use std::time::{Duration, Instant};
 
+use clap::{value_parser, Parser, ValueEnum};
+use log::{LevelFilter, Log, Metadata, Record};
 use thousands::Separable;
 
-use crate::bot::Bot;
-use crate::game::connect4::ConnectFour;
-use crate::game::player::Player;
-use crate::game::Game;
+use min_max::game::connect4::solver::{Position, Solver};
+use min_max::mcts::config::MctsConfig;
+use min_max::mcts::MctsBudget;
+use min_max::min_max::config::{Aspiration, DepthPenalty, PlayOrder, SearchConfig};
+use min_max::min_max::transposition::TranspositionTable;
+use min_max::min_max::Algorithm;
+use min_max::{Bot, ConnectFour, Game, MctsBot, Outcome, Player, SearchLimit, Solution};
 
-mod bot;
-mod game;
-mod min_max;
-mod scalar;
+/// Bytes in a MiB, the unit of --table-memory
+const MIB: usize = 1024 * 1024;
 
-fn main() {
-    let max_depth = 11;
-    let bot_vs_bot = false;
+/// Play Connect Four in the terminal, against the min-max bot or between bots
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    /// Number of plays the bots explore ahead
+    #[arg(short, long, default_value_t = 11, value_parser = value_parser!(u32).range(1..=42))]
+    depth: u32,
 
-    let worst_case: u64 = (1..=max_depth).into_iter().map(|n| 7u64.pow(n)).sum();
-    println!(
-        "Max depth: {max_depth} -> Worst case: 7^{} + 7^{} + ... + 7^1) = {} nodes",
-        max_depth,
-        max_depth - 1,
-        worst_case.separate_with_commas()
-    );
+    /// Time the bots may think for each play, in milliseconds, instead of a fixed depth
+    #[arg(short = 't', long, conflicts_with = "depth", value_parser = value_parser!(u64).range(1..))]
+    move_time: Option<u64>,
+
+    /// Who plays the game
+    #[arg(short, long, value_enum, default_value_t = Mode::HumanVsBot)]
+    mode: Mode,
+
+    /// Player (1 or 2) played by the bot in a human-vs-bot game
+    #[arg(short, long, default_value_t = 2, value_parser = value_parser!(u8).range(1..=2))]
+    bot_player: u8,
+
+    /// How the bot of player 1 chooses its plays
+    #[arg(long, value_enum, default_value_t = Engine::MinMax)]
+    p1_engine: Engine,
+
+    /// How the bot of player 2 chooses its plays
+    #[arg(long, value_enum, default_value_t = Engine::MinMax)]
+    p2_engine: Engine,
+
+    /// Number of random games the Monte Carlo bots play before each play, unless --move-time is
+    /// given
+    #[arg(long, default_value_t = 20_000, value_parser = value_parser!(u32).range(1..))]
+    mcts_iterations: u32,
+
+    /// Exploration constant of the Monte Carlo bots: the higher, the more they try the plays that
+    /// look bad
+    #[arg(long, default_value_t = std::f64::consts::SQRT_2)]
+    exploration: f64,
+
+    /// Player (1 or 2) who plays first. If not given, a human-vs-bot game asks you before each game,
+    /// and player 1 starts the other games
+    #[arg(short, long, value_parser = value_parser!(u8).range(1..=2))]
+    first: Option<u8>,
+
+    /// Number of games to play
+    #[arg(short, long, default_value_t = 1, value_parser = value_parser!(u32).range(1..))]
+    games: u32,
+
+    /// Seed of the random choices of the bots, to replay the same games with any number of threads
+    /// (but not with --move-time, as the bots then explore as deep as they can in time). Random if
+    /// not given
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// Number of threads used by each bot. All the cores if not given
+    #[arg(short = 'j', long, value_parser = value_parser!(u32).range(1..))]
+    threads: Option<u32>,
+
+    /// How the bots explore the plays
+    #[arg(long, value_enum, default_value_t = SearchAlgorithm::AlphaBeta)]
+    algorithm: SearchAlgorithm,
+
+    /// How much the bots dislike draws, from -2147483647 (they like draws as much as wins) to
+    /// 2147483647 (they dislike them as much as losses). 0 to score draws like even positions. Half
+    /// of a win if not given
+    #[arg(long, allow_negative_numbers = true, value_parser = value_parser!(i32).range(-i32::MAX as i64..))]
+    contempt: Option<i32>,
+
+    /// How much the bots lower the weight of a position for each play needed to reach it, so that
+    /// they win as fast as possible. 0 to disable
+    #[arg(long, default_value_t = 1, value_parser = value_parser!(i32).range(0..))]
+    depth_penalty: i32,
+
+    /// Only score the positions where the bots stop exploring by their winner, instead of how
+    /// close each player is to winning
+    #[arg(long)]
+    no_game_score: bool,
+
+    /// Explore the plays that are as good as each other in a fixed order instead of a random
+    /// one, so that the bots always play the same way
+    #[arg(long, conflicts_with = "seed")]
+    fixed_order: bool,
+
+    /// Plays left to explore below a position for the threads to share the exploration of its
+    /// plays
+    #[arg(long, default_value_t = 4)]
+    min_split_depth: u32,
+
+    /// Half of the width of the window each exploration of the bots starts with, around the
+    /// weight found by the previous one. 0 to explore with the full window
+    #[arg(long, default_value_t = 100, value_parser = value_parser!(i32).range(0..))]
+    aspiration_width: i32,
+
+    /// How many times further the window of an exploration is moved when the weight falls
+    /// outside of it, before exploring again
+    #[arg(long, default_value_t = 4, value_parser = value_parser!(i32).range(1..))]
+    aspiration_growth: i32,
+
+    /// Number of empty cells from which the bots explore until the end of the game, whatever
+    /// their depth, to find out who wins with perfect play. 0 to always stop at the depth
+    #[arg(
+        long,
+        default_value_t = SearchConfig::<i32>::default().endgame_plays(),
+        value_parser = value_parser!(u32).range(0..=42)
+    )]
+    endgame_plays: u32,
+
+    /// Time the bots may explore past --depth to reach the end of the game (see
+    /// --endgame-plays), in milliseconds. Past it, they play the best play of their depth
+    #[arg(
+        long,
+        conflicts_with = "move_time",
+        default_value_t = SearchConfig::<i32>::default().endgame_time().as_millis() as u64
+    )]
+    endgame_time: u64,
+
+    /// Memory used by each bot to remember explored positions, in MiB
+    #[arg(
+        long,
+        default_value_t = (TranspositionTable::<ConnectFour>::DEFAULT_MEMORY / MIB) as u32,
+        value_parser = value_parser!(u32).range(1..)
+    )]
+    table_memory: u32,
+
+    /// Print the plays each bot expects after its own play, like "bot expects: 4 4 3 5"
+    #[arg(short = 'e', long)]
+    show_expected: bool,
 
-    if bot_vs_bot {
-        let mut times: Vec<u64> = Vec::new();
-        loop {
-            let time = game(max_depth, bot_vs_bot);
-            times.push(time);
-            println!(
-                "Average time: {}ms",
-                times.iter().sum::<u64>() / times.len() as u64
-            );
+    /// Before each play of a bot, print how it rates every possible play (explored as deep as
+    /// --depth, which takes longer than playing)
+    #[arg(short, long, conflicts_with = "move_time")]
+    analyze: bool,
+
+    /// Instead of playing, print the exact result of the position after the given columns were
+    /// played from the start (like 4435), and of each possible play, then exit
+    #[arg(long, value_name = "COLUMNS")]
+    solve: Option<String>,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    HumanVsBot,
+    BotVsBot,
+    HumanVsHuman,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+enum Engine {
+    /// Min-max search, see the search options below
+    MinMax,
+    /// Monte Carlo tree search, which rates the plays by playing random games
+    Mcts,
+}
+
+#[derive(ValueEnum, Debug, Copy, Clone, PartialEq, Eq)]
+enum SearchAlgorithm {
+    AlphaBeta,
+    /// Principal variation search
+    Pvs,
+}
+
+impl From<SearchAlgorithm> for Algorithm {
+    fn from(algorithm: SearchAlgorithm) -> Self {
+        match algorithm {
+            SearchAlgorithm::AlphaBeta => Algorithm::AlphaBeta,
+            SearchAlgorithm::Pvs => Algorithm::PrincipalVariation,
         }
+    }
+}
+
+/// Prints what the bots are doing between the boards
+struct StdoutLogger;
+
+impl Log for StdoutLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            println!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StdoutLogger = StdoutLogger;
+
+fn main() {
+    let cli = Cli::parse();
+
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(if cfg!(debug_assertions) {
+        LevelFilter::Debug
     } else {
-        let time = game(max_depth, bot_vs_bot);
-        println!("Average time: {}ms", time);
+        LevelFilter::Info
+    });
+
+    if let Some(columns) = &cli.solve {
+        solve(columns);
+        return;
+    }
+
+    if cli.mode != Mode::HumanVsHuman && cli.move_time.is_none() {
+        let max_depth = cli.depth;
+        let width = ConnectFour::WIDTH;
+        let worst_case: u64 = (1..=max_depth).map(|n| (width as u64).pow(n)).sum();
+        println!(
+            "Max depth: {max_depth} -> Worst case: {width}^{} + {width}^{} + ... + {width}^1) = {} nodes",
+            max_depth,
+            max_depth - 1,
+            worst_case.separate_with_commas()
+        );
+    }
+
+    let mut averages: Vec<(u64, Option<f64>)> = Vec::new();
+    for game_index in 0..cli.games {
+        averages.extend(game(&cli, game_index));
+        if !averages.is_empty() {
+            let time = averages.iter().map(|(time, _)| time).sum::<u64>() / averages.len() as u64;
+            let researches: Vec<f64> = averages
+                .iter()
+                .filter_map(|(_, researches)| *researches)
+                .collect();
+            if researches.is_empty() {
+                println!("Average time: {time}ms");
+            } else {
+                println!(
+                    "Average time: {time}ms, {:.2} aspiration re-searches per play of the min-max bots",
+                    researches.iter().sum::<f64>() / researches.len() as f64
+                );
+            }
+        }
     }
 }
 
-fn game(max_depth: u32, bot_vs_bot: bool) -> u64 {
+/// Who plays for a player
+enum Side {
+    Human,
+    Bot(Box<Bot<ConnectFour>>),
+    Mcts(Box<MctsBot<ConnectFour>>),
+}
+
+impl Side {
+    /// Tells a bot what its opponent played
+    fn other_played(&mut self, play: NonZeroUsize) {
+        match self {
+            Side::Human => {}
+            Side::Bot(bot) => bot.other_played(play).unwrap(),
+            Side::Mcts(bot) => bot.other_played(play).unwrap(),
+        }
+    }
+}
+
+/// The configuration of the searches of the bots for the game `game_index`
+fn search_config(cli: &Cli, game_index: u32) -> SearchConfig<i32> {
+    let play_order = match cli.seed {
+        // each game has its own seed, so that they are not all the same
+        Some(seed) => PlayOrder::Seeded(seed.wrapping_add(game_index as u64)),
+        None if cli.fixed_order => PlayOrder::Fixed,
+        None => PlayOrder::Random,
+    };
+    let depth_penalty = match cli.depth_penalty {
+        0 => DepthPenalty::None,
+        penalty => DepthPenalty::PerPly(penalty),
+    };
+    let aspiration = match cli.aspiration_width {
+        0 => Aspiration::None,
+        width => Aspiration::Window {
+            initial_width: width,
+            growth: cli.aspiration_growth,
+        },
+    };
+    let mut config = SearchConfig::default()
+        .with_algorithm(cli.algorithm.into())
+        .with_game_score(!cli.no_game_score)
+        .with_depth_penalty(depth_penalty)
+        .with_play_order(play_order)
+        .with_min_split_depth(cli.min_split_depth)
+        .with_aspiration(aspiration)
+        .with_endgame_plays(cli.endgame_plays)
+        .with_endgame_time(Duration::from_millis(cli.endgame_time));
+    if let Some(contempt) = cli.contempt {
+        config = config.with_contempt(contempt);
+    }
+    config
+}
+
+/// The configuration of the Monte Carlo bots for the game `game_index`, see `search_config`
+fn mcts_config(cli: &Cli, game_index: u32) -> MctsConfig {
+    let config = MctsConfig::default().with_exploration(cli.exploration);
+    match cli.seed {
+        Some(seed) => config.with_seed(seed.wrapping_add(game_index as u64)),
+        None => config,
+    }
+}
+
+/// Plays the game `game_index` and returns the average time of each bot, with the average
+/// aspiration re-searches of the min-max bots
+fn game(cli: &Cli, game_index: u32) -> Vec<(u64, Option<f64>)> {
     let p1 = NonZeroU8::new(1).unwrap();
     let p2 = NonZeroU8::new(2).unwrap();
+    let config = search_config(cli, game_index);
 
-    let bot_player: NonZeroU8 = p2;
-
-    let mut current_player = if bot_vs_bot || ask_start() { p1 } else { p2 };
+    let limit = match cli.move_time {
+        Some(move_time) => SearchLimit::MoveTime(Duration::from_millis(move_time)),
+        None => SearchLimit::Depth(cli.depth),
+    };
+    let budget = match cli.move_time {
+        Some(move_time) => MctsBudget::MoveTime(Duration::from_millis(move_time)),
+        None => MctsBudget::Iterations(cli.mcts_iterations),
+    };
+    let new_bot = |player| {
+        let engine = if player == p1 {
+            cli.p1_engine
+        } else {
+            cli.p2_engine
+        };
+        if engine == Engine::Mcts {
+            let mut bot = MctsBot::new(player, budget).with_config(mcts_config(cli, game_index));
+            if let Some(threads) = cli.threads {
+                bot = bot.with_threads(threads as usize);
+            }
+            return Side::Mcts(Box::new(bot));
+        }
+        let mut bot = Bot::with_limit(player, limit)
+            .with_table_memory(cli.table_memory as usize * MIB)
+            .with_config(config);
+        if let Some(threads) = cli.threads {
+            bot = bot.with_threads(threads as usize);
+        }
+        Side::Bot(Box::new(bot))
+    };
+    let bot_player = NonZeroU8::new(cli.bot_player).unwrap();
+    let mut sides = match cli.mode {
+        Mode::HumanVsBot if bot_player == p1 => [new_bot(p1), Side::Human],
+        Mode::HumanVsBot => [Side::Human, new_bot(p2)],
+        Mode::BotVsBot => [new_bot(p1), new_bot(p2)],
+        Mode::HumanVsHuman => [Side::Human, Side::Human],
+    };
 
-    let mut bot: Bot<ConnectFour> = Bot::new(bot_player, max_depth);
-    let mut other_bot: Bot<ConnectFour> = Bot::new(bot_player.other(), max_depth);
+    let mut current_player = match cli.first {
+        Some(first) => NonZeroU8::new(first).unwrap(),
+        None if cli.mode == Mode::HumanVsBot && !ask_start() => bot_player,
+        None if cli.mode == Mode::HumanVsBot => bot_player.other(),
+        None => p1,
+    };
 
+    let mut game = ConnectFour::new();
     let mut p1_score: i32 = 0;
     loop {
         println!();
-        bot.expect_game().print();
+        game.print();
         #[cfg(debug_assertions)]
         {
-            let p2_score = bot.expect_game().get_score(p2);
+            let p2_score = game.get_score(p2);
             assert_eq!(p1_score, -p2_score);
         }
         println!("Scores: {p1_score} for player 1");
         println!();
         println!("Player {current_player}'s turn");
-        if current_player == bot_player {
-            let play = bot.play();
-            if bot_vs_bot {
-                other_bot.other_played(play).unwrap();
-            }
-        } else {
-            if bot_vs_bot {
-                let play = other_bot.play();
-                bot.other_played(play).unwrap();
-            } else {
-                let result = player_play(&mut bot);
-                if let Err(err) = result {
-                    println!("Invalid move: {err}\n");
-                    continue;
+
+        let index = current_player.get() as usize - 1;
+        let play = match &mut sides[index] {
+            Side::Bot(bot) => {
+                if cli.analyze {
+                    print_analysis(bot, cli.depth);
+                }
+                let play = bot.play();
+                if cli.show_expected {
+                    let expected: Vec<String> = bot
+                        .principal_variation()
+                        .iter()
+                        .skip(1)
+                        .map(|(play, _)| play.to_string())
+                        .collect();
+                    println!("Bot expects: {}", expected.join(" "));
                 }
+                // a win in 1 play is announced by the end of the game
+                match bot.solution() {
+                    Some(solution @ (Solution::Win(2..) | Solution::Loss(_))) => println!(
+                        "Player {current_player} has a {}",
+                        describe_solution(solution)
+                    ),
+                    Some(Solution::Draw) => println!("The game is a draw with perfect play"),
+                    _ => {}
+                }
+                play
             }
+            Side::Mcts(bot) => bot.play(),
+            Side::Human => NonZeroUsize::new(get_user_input()).unwrap(),
+        };
+        if let Err(err) = game.play(current_player, play) {
+            println!("Invalid move: {err}\n");
+            continue;
         }
-
-        let game = bot.expect_game();
+        sides[1 - index].other_played(play);
 
         p1_score = game.get_score(p1);
 
-        if p1_score == <ConnectFour as Game>::Score::MAX
-            || p1_score == <ConnectFour as Game>::Score::MIN
-        {
-            println!("Player {current_player} won!\n");
+        if let Some(winner) = game.get_winner() {
+            println!("Player {winner} won!\n");
             game.print();
             break;
         }
@@ -101,19 +430,94 @@ fn game(max_depth: u32, bot_vs_bot: bool) -> u64 {
 
         current_player = current_player.other();
     }
-    bot.average_time()
+    sides
+        .iter()
+        .filter_map(|side| match side {
+            Side::Bot(bot) => Some((
+                bot.average_time(),
+                Some(bot.average_aspiration_researches()),
+            )),
+            Side::Mcts(bot) => Some((bot.average_time(), None)),
+            Side::Human => None,
+        })
+        .collect()
+}
+
+/// Prints the weight of each play of the bot and the plays it expects after it
+fn print_analysis(bot: &mut Bot<ConnectFour>, depth: u32) {
+    let Some(analysis) = bot.analyze(depth) else {
+        return;
+    };
+    println!("Plays rated by the bot:");
+    for analyzed in analysis {
+        let expected: Vec<String> = analyzed
+            .variation
+            .iter()
+            .skip(1)
+            .map(|(play, _)| play.to_string())
+            .collect();
+        println!(
+            "  {}: {} (then {})",
+            analyzed.play,
+            describe_weight(Outcome::decode(analyzed.weight)),
+            expected.join(" ")
+        );
+    }
+}
+
+/// The outcome of a weight in words, counting the moves of the player who wins
+fn describe_weight(outcome: Outcome<i32>) -> String {
+    let moves = |plies: u32| match plies.div_ceil(2) {
+        1 => "1 move".to_string(),
+        moves => format!("{moves} moves"),
+    };
+    match outcome {
+        Outcome::Win(plies) => format!("forced win in {}", moves(plies)),
+        Outcome::Loss(plies) => format!("forced loss in {}", moves(plies)),
+        Outcome::Heuristic(weight) => weight.to_string(),
+    }
 }
 
-fn player_play(bot: &mut Bot<ConnectFour>) -> Result<(), &str> {
-    let column = get_user_input();
-    bot.other_played(NonZeroUsize::new(column).unwrap())
+/// Prints the exact result of the position after `columns`, and of each play from it
+fn solve(columns: &str) {
+    let position = match Position::from_columns(columns) {
+        Ok(position) => position,
+        Err(error) => {
+            println!("Invalid position: {error}");
+            return;
+        }
+    };
+    let mut solver = Solver::default();
+    let start = Instant::now();
+    let solution = solver.solve(&position);
+    println!(
+        "Player {} to play: {}",
+        position.moves() % 2 + 1,
+        describe_solution(solution)
+    );
+    for (play, solution) in solver.analyze(&position) {
+        println!("  {play}: {}", describe_solution(solution));
+    }
+    println!(
+        "Solved in {}ms, {} positions",
+        start.elapsed().as_millis(),
+        solver.nodes().separate_with_commas()
+    );
+}
+
+/// The exact result of a position in words, see `describe_weight`
+fn describe_solution(solution: Solution) -> String {
+    match solution {
+        Solution::Win(plies) => describe_weight(Outcome::Win(plies)),
+        Solution::Loss(plies) => describe_weight(Outcome::Loss(plies)),
+        Solution::Draw => "draw".to_string(),
+    }
 }
 
 fn ask_start() -> bool {
     loop {
         println!("Do you want to start? (y/n)");
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+        let input = read_line();
         let input: Option<char> = input.trim().chars().next();
 
         if let Some(c) = input {
@@ -128,13 +532,12 @@ fn ask_start() -> bool {
 
 fn get_user_input() -> usize {
     loop {
-        println!("please specify a column from 1 to 7:");
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+        println!("please specify a column from 1 to {}:", ConnectFour::WIDTH);
+        let input = read_line();
         let input: Option<usize> = input.trim().parse().ok();
 
         if let Some(column) = input {
-            if column == 0 || column > 7 {
+            if column == 0 || column > ConnectFour::WIDTH {
                 println!("Invalid move: {column}\n");
                 continue;
             }
@@ -142,3 +545,12 @@ fn get_user_input() -> usize {
         }
     }
 }
+
+/// Reads a line of the terminal, and quits if there is nothing more to read
+fn read_line() -> String {
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input).unwrap() == 0 {
+        std::process::exit(0);
+    }
+    input
+}
@@ -0,0 +1,244 @@
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::iter::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::bot::install;
+use crate::game::player::Player;
+use crate::game::Game;
+use crate::mcts::config::MctsConfig;
+use crate::mcts::tree::Tree;
+
+pub mod config;
+mod tests;
+mod tree;
+
+/// How long a Monte Carlo tree search runs, see `analyze`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MctsBudget {
+    /// Runs this number of iterations, shared between the trees
+    Iterations(u32),
+    /// Runs iterations until the time is over
+    MoveTime(Duration),
+}
+
+/// How much a play was explored by a Monte Carlo tree search, see `analyze`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MctsPlay<G: Game> {
+    pub play: G::InputCoordinate,
+    /// Number of iterations that went through this play, in all the trees
+    pub visits: u32,
+    /// Average result of the playouts after this play for the player making it: 1 if they
+    /// always won, 0.5 for draws or as many wins as losses, 0 if they always lost
+    pub win_rate: f64,
+}
+
+/// What happened during a Monte Carlo tree search, see `analyze`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MctsStats {
+    /// Number of iterations, each adding a position to a tree and playing a random game from it
+    pub iterations: u64,
+    /// Number of trees built in parallel, see `MctsConfig::with_trees`
+    pub trees: usize,
+    pub elapsed: Duration,
+}
+
+impl Display for MctsStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.elapsed.as_secs_f64();
+        let per_second = if seconds == 0.0 {
+            0.0
+        } else {
+            self.iterations as f64 / seconds
+        };
+        write!(
+            f,
+            "{} iterations in {}ms ({:.0} iterations/s), {} trees",
+            self.iterations,
+            self.elapsed.as_millis(),
+            per_second,
+            self.trees,
+        )
+    }
+}
+
+/// Monte Carlo tree search (UCT) of the plays of `player`, who must be the next one to play in
+/// `game`, within `budget`. Unlike the min-max search, it needs no evaluator and never uses
+/// `Game::get_score`: the positions are rated by playing random games from them until the end.
+///
+/// The trees (see `MctsConfig::with_trees`) are built in parallel in the current rayon pool.
+/// Returns the plays from the most visited to the least visited, which is the order of their
+/// strength, and the stats of the search.
+pub fn analyze<G: Game>(
+    game: &G,
+    player: G::Player,
+    budget: MctsBudget,
+    config: &MctsConfig,
+) -> (Vec<MctsPlay<G>>, MctsStats) {
+    let start = Instant::now();
+    let trees = config.trees();
+    let build_tree = |index: usize| {
+        let mut rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ game.hash_key() ^ index as u64),
+            None => StdRng::from_entropy(),
+        };
+        let mut tree = Tree::new(game, player, config.exploration);
+        let mut iterations = 0;
+        loop {
+            let done = match budget {
+                // the first trees get the remaining iterations
+                MctsBudget::Iterations(total) => {
+                    let share =
+                        total as usize / trees + usize::from(index < total as usize % trees);
+                    iterations >= share as u64
+                }
+                MctsBudget::MoveTime(move_time) => start.elapsed() >= move_time,
+            };
+            if done {
+                break;
+            }
+            tree.iterate(game, &mut rng);
+            iterations += 1;
+        }
+        (tree.root_plays(), iterations)
+    };
+    let results: Vec<_> = (0..trees).into_par_iter().map(build_tree).collect();
+
+    let mut analysis: Vec<(G::InputCoordinate, u32, f64)> = Vec::new();
+    for (play, visits, reward) in results.iter().flat_map(|(plays, _)| plays) {
+        match analysis.iter_mut().find(|(other, _, _)| other == play) {
+            Some((_, total_visits, total_reward)) => {
+                *total_visits += visits;
+                *total_reward += reward;
+            }
+            None => analysis.push((*play, *visits, *reward)),
+        }
+    }
+    // the order of the plays breaks the ties, as the trees may have been built in any order
+    analysis.sort_by_key(|(play, visits, _)| (std::cmp::Reverse(*visits), *play));
+    let analysis = analysis
+        .into_iter()
+        .map(|(play, visits, reward)| MctsPlay {
+            play,
+            visits,
+            win_rate: reward / visits as f64,
+        })
+        .collect();
+
+    let stats = MctsStats {
+        iterations: results.iter().map(|(_, iterations)| iterations).sum(),
+        trees,
+        elapsed: start.elapsed(),
+    };
+    log::debug!("{stats}");
+    (analysis, stats)
+}
+
+/// Plays like `Bot`, with a Monte Carlo tree search instead of a min-max search (see `analyze`),
+/// so it can play games without a good `Game::get_score`. Each play starts a new search.
+pub struct MctsBot<G: Game> {
+    player: G::Player,
+    game: G,
+    budget: MctsBudget,
+    config: MctsConfig,
+    times: Vec<u64>,
+    /// The stats of the search of each play, see `search_stats`
+    stats: Vec<MctsStats>,
+    /// The plays rated by the last search, see `analysis`
+    analysis: Vec<MctsPlay<G>>,
+    /// The threads of the searches, or None to use the global rayon pool
+    thread_pool: Option<ThreadPool>,
+}
+
+impl<G: Game> MctsBot<G> {
+    pub fn new(player: G::Player, budget: MctsBudget) -> Self {
+        Self {
+            player,
+            game: G::new(),
+            budget,
+            config: MctsConfig::default(),
+            times: Vec::new(),
+            stats: Vec::new(),
+            analysis: Vec::new(),
+            thread_pool: None,
+        }
+    }
+
+    /// Searches as configured by `config` instead of the default configuration
+    pub fn with_config(mut self, config: MctsConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Searches with `threads` threads of its own instead of the global rayon pool, see
+    /// `Bot::with_threads`
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("Could not create the threads of the bot");
+        self.thread_pool = Some(thread_pool);
+        self
+    }
+
+    pub fn other_played(&mut self, play: G::InputCoordinate) -> Result<(), &str> {
+        self.game.play(self.player.other(), play)
+    }
+
+    /// Chooses and makes the play of the bot. The game must not be over.
+    pub fn play(&mut self) -> G::InputCoordinate {
+        assert!(
+            self.game.get_winner().is_none() && !self.game.is_full(),
+            "the game is over"
+        );
+        let start = Instant::now();
+        log::info!("Playing random games...");
+        let (analysis, stats) = install(self.thread_pool.as_ref(), || {
+            analyze(&self.game, self.player, self.budget, &self.config)
+        });
+        let (play, win_rate) = match analysis.first() {
+            Some(best) => (best.play, best.win_rate),
+            // no time for a single iteration
+            None => (self.game.possible_plays()[0], 0.5),
+        };
+        self.game
+            .play(self.player, play)
+            .expect("play should be possible");
+
+        let time = start.elapsed().as_millis() as u64;
+        self.times.push(time);
+        log::info!(
+            "Done in {time}ms, {} iterations, expects to win {:.0}% of the games",
+            stats.iterations,
+            win_rate * 100.0
+        );
+        self.stats.push(stats);
+        self.analysis = analysis;
+        play
+    }
+
+    /// The plays rated by the search of the last play, from the best to the worst, see `analyze`
+    pub fn analysis(&self) -> &[MctsPlay<G>] {
+        &self.analysis
+    }
+
+    /// The stats of the search of each play of the bot
+    pub fn search_stats(&self) -> &[MctsStats] {
+        &self.stats
+    }
+
+    /// The average time the bot took to play, 0 if it did not play yet
+    pub fn average_time(&self) -> u64 {
+        if self.times.is_empty() {
+            return 0;
+        }
+        self.times.iter().sum::<u64>() / self.times.len() as u64
+    }
+
+    pub fn expect_game(&self) -> &G {
+        &self.game
+    }
+}
@@ -0,0 +1,53 @@
+/// How a Monte Carlo tree search explores the plays, see `mcts::analyze`. Built from the default
+/// one with the `with_*` methods:
+///
+/// ```
+/// use min_max::mcts::config::MctsConfig;
+///
+/// let config = MctsConfig::default().with_exploration(1.0).with_seed(42);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MctsConfig {
+    pub(crate) exploration: f64,
+    pub(crate) trees: Option<usize>,
+    pub(crate) seed: Option<u64>,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            exploration: std::f64::consts::SQRT_2,
+            trees: None,
+            seed: None,
+        }
+    }
+}
+
+impl MctsConfig {
+    /// The exploration constant of UCT: the higher, the more the search tries the plays that
+    /// looked bad so far instead of the best ones. √2 by default.
+    pub fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    /// Root parallelism: the search builds `trees` independent trees in parallel, and adds their
+    /// visits of each play. One per thread of the current rayon pool by default.
+    pub fn with_trees(mut self, trees: usize) -> Self {
+        self.trees = Some(trees);
+        self
+    }
+
+    /// Makes the random playouts depend only on `seed` and on the position, so that a search with
+    /// a number of iterations (see `MctsBudget`) and a number of trees (see `with_trees`) always
+    /// gives the same result, whatever the number of threads. Random if not given.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The number of trees built by a search, see `with_trees`
+    pub(crate) fn trees(&self) -> usize {
+        self.trees.unwrap_or_else(rayon::current_num_threads).max(1)
+    }
+}
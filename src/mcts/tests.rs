@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod mcts_tests {
+    use std::num::{NonZeroU8, NonZeroUsize};
+    use std::time::{Duration, Instant};
+
+    use crate::bot::Bot;
+    use crate::game::connect4::{ConnectFour, ConnectN};
+    use crate::game::player::Player;
+    use crate::game::Game;
+    use crate::mcts::config::MctsConfig;
+    use crate::mcts::{analyze, MctsBot, MctsBudget};
+
+    /// The game after the given columns were played from the start, and the next player
+    fn play_columns(columns: &str) -> (ConnectFour, NonZeroU8) {
+        let mut game = ConnectFour::new();
+        let mut player = NonZeroU8::new(1).unwrap();
+        for column in columns.chars() {
+            let column = column.to_digit(10).unwrap() as usize;
+            game.play(player, NonZeroUsize::new(column).unwrap())
+                .unwrap();
+            player = player.other();
+        }
+        (game, player)
+    }
+
+    #[test]
+    fn finds_win_and_block() {
+        let config = MctsConfig::default().with_seed(0);
+        // player 1 aligned 1, 2 and 3 at the bottom: player 2 blocks at 4, or player 1 wins there
+        for (columns, expected) in [("15263", 4), ("152637", 4)] {
+            let (game, player) = play_columns(columns);
+            let (analysis, _) = analyze(&game, player, MctsBudget::Iterations(3000), &config);
+            assert_eq!(analysis[0].play.get(), expected, "plays {columns}");
+        }
+        let (game, player) = play_columns("152637");
+        let (analysis, _) = analyze(&game, player, MctsBudget::Iterations(3000), &config);
+        // the win is found by every playout through it
+        assert_eq!(analysis[0].win_rate, 1.0);
+    }
+
+    #[test]
+    fn iteration_budget() {
+        let game = ConnectFour::new();
+        let p1 = NonZeroU8::new(1).unwrap();
+        for trees in [1, 3] {
+            let config = MctsConfig::default().with_trees(trees);
+            let (analysis, stats) = analyze(&game, p1, MctsBudget::Iterations(1000), &config);
+            assert_eq!(stats.iterations, 1000);
+            assert_eq!(stats.trees, trees);
+            // each iteration goes through one of the plays of the root
+            assert_eq!(analysis.iter().map(|play| play.visits).sum::<u32>(), 1000);
+            assert_eq!(analysis.len(), game.possible_plays().len());
+            assert!(analysis.windows(2).all(|w| w[0].visits >= w[1].visits));
+            assert!(analysis
+                .iter()
+                .all(|play| (0.0..=1.0).contains(&play.win_rate)));
+        }
+    }
+
+    #[test]
+    fn time_budget() {
+        let game = ConnectFour::new();
+        let p1 = NonZeroU8::new(1).unwrap();
+        let start = Instant::now();
+        let (analysis, stats) = analyze(
+            &game,
+            p1,
+            MctsBudget::MoveTime(Duration::from_millis(200)),
+            &MctsConfig::default(),
+        );
+        assert!(start.elapsed() < Duration::from_millis(400));
+        assert!(stats.iterations > 0);
+        assert!(!analysis.is_empty());
+    }
+
+    #[test]
+    fn same_seed_same_search() {
+        let (game, player) = play_columns("4435");
+        let config = MctsConfig::default().with_seed(7).with_trees(4);
+        let search = |threads| {
+            let thread_pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            thread_pool.install(|| analyze(&game, player, MctsBudget::Iterations(2000), &config).0)
+        };
+        assert_eq!(search(1), search(3));
+    }
+
+    #[test]
+    fn plays_any_game() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        // the first player wins by playing in the middle, see connect_n_search
+        let game = ConnectN::<4, 3, 3>::new();
+        let config = MctsConfig::default().with_seed(0);
+        let (analysis, _) = analyze(&game, p1, MctsBudget::Iterations(20_000), &config);
+        assert!([2, 3].contains(&analysis[0].play.get()));
+    }
+
+    #[test]
+    fn mcts_against_min_max() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+        let mut mcts: MctsBot<ConnectFour> = MctsBot::new(p1, MctsBudget::Iterations(200))
+            .with_config(MctsConfig::default().with_seed(0));
+        let mut min_max: Bot<ConnectFour> = Bot::new(p2, 2);
+        let mut game = ConnectFour::new();
+        let mut player = p1;
+        let mut mcts_plays = 0;
+        while game.get_winner().is_none() && !game.is_full() {
+            let play = if player == p1 {
+                let play = mcts.play();
+                mcts_plays += 1;
+                min_max.other_played(play).unwrap();
+                play
+            } else {
+                let play = min_max.play();
+                mcts.other_played(play).unwrap();
+                play
+            };
+            game.play(player, play).unwrap();
+            assert_eq!(mcts.expect_game().last_play(), Some(play));
+            player = player.other();
+        }
+        assert_eq!(mcts.search_stats().len(), mcts_plays);
+        assert!(mcts
+            .search_stats()
+            .iter()
+            .all(|stats| stats.iterations == 200));
+    }
+
+    #[test]
+    fn average_time_before_playing() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mcts: MctsBot<ConnectFour> = MctsBot::new(p1, MctsBudget::Iterations(10));
+        assert_eq!(mcts.average_time(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "the game is over")]
+    fn no_play_after_the_end() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let mut mcts: MctsBot<ConnectFour> = MctsBot::new(p1, MctsBudget::Iterations(10));
+        // player 1 aligned 4 in the first column
+        mcts.game = play_columns("1212121").0;
+        mcts.play();
+    }
+}
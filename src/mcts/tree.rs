@@ -0,0 +1,154 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::game::player::Player;
+use crate::game::Game;
+
+/// A position of the search tree, see `Tree`
+struct Node<G: Game> {
+    /// The play leading to this node from its parent, None for the root
+    play: Option<G::InputCoordinate>,
+    /// The player who made `play`, from whose point of view `reward` is counted
+    player: G::Player,
+    /// Indexes of the children in `Tree::nodes`
+    children: Vec<usize>,
+    /// The possible plays that have no child yet. Empty when the game is over.
+    untried: Vec<G::InputCoordinate>,
+    visits: u32,
+    /// Sum of the results of the playouts through this node for `player`: 1 for a win, 0.5 for a
+    /// draw and 0 for a loss
+    reward: f64,
+}
+
+impl<G: Game> Node<G> {
+    fn new(game: &G, play: Option<G::InputCoordinate>, player: G::Player) -> Self {
+        let untried = if game.get_winner().is_some() || game.is_full() {
+            Vec::new()
+        } else {
+            game.possible_plays()
+        };
+        Self {
+            play,
+            player,
+            children: Vec::new(),
+            untried,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+
+    /// Upper confidence bound of the reward of this node, which is visited by a parent visited
+    /// `parent_visits` times (UCT)
+    fn upper_bound(&self, parent_visits: u32, exploration: f64) -> f64 {
+        let visits = self.visits as f64;
+        self.reward / visits + exploration * ((parent_visits as f64).ln() / visits).sqrt()
+    }
+}
+
+/// The tree of a Monte Carlo tree search, whose nodes are stored in a single vector and created
+/// one per iteration
+pub(crate) struct Tree<G: Game> {
+    nodes: Vec<Node<G>>,
+    exploration: f64,
+}
+
+impl<G: Game> Tree<G> {
+    /// Creates the tree of `game`, where `player` plays next
+    pub(crate) fn new(game: &G, player: G::Player, exploration: f64) -> Self {
+        Self {
+            nodes: vec![Node::new(game, None, player.other())],
+            exploration,
+        }
+    }
+
+    /// One iteration of the search from `game`, the game of the root:
+    ///
+    /// - selection: goes down the tree, choosing the child with the best upper bound each time,
+    ///   until a node has plays without child
+    /// - expansion: creates the child of one of these plays, chosen at random
+    /// - simulation: plays at random from this child until the end of the game
+    /// - backpropagation: adds the result to the nodes on the way
+    pub(crate) fn iterate(&mut self, game: &G, rng: &mut impl Rng) {
+        let mut game = game.clone();
+        let mut path = vec![0];
+        let mut index = 0;
+        while self.nodes[index].untried.is_empty() && !self.nodes[index].children.is_empty() {
+            index = self.select_child(index);
+            let node = &self.nodes[index];
+            game.play(node.player, node.play.unwrap())
+                .expect("the plays of the tree should be possible");
+            path.push(index);
+        }
+
+        let parent = &mut self.nodes[index];
+        if !parent.untried.is_empty() {
+            let play = parent
+                .untried
+                .swap_remove(rng.gen_range(0..parent.untried.len()));
+            let player = parent.player.other();
+            game.play(player, play)
+                .expect("the untried plays should be possible");
+            let child = self.nodes.len();
+            self.nodes[index].children.push(child);
+            self.nodes.push(Node::new(&game, Some(play), player));
+            path.push(child);
+            index = child;
+        }
+
+        let winner = playout(&mut game, self.nodes[index].player, rng);
+        for index in path {
+            let node = &mut self.nodes[index];
+            node.visits += 1;
+            node.reward += match winner {
+                Some(winner) if winner == node.player => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+    }
+
+    /// The child of the node at `index` with the best upper bound, the first one if several are
+    /// as good
+    fn select_child(&self, index: usize) -> usize {
+        let parent = &self.nodes[index];
+        let mut best = (f64::NEG_INFINITY, parent.children[0]);
+        for &child in &parent.children {
+            let bound = self.nodes[child].upper_bound(parent.visits, self.exploration);
+            if bound > best.0 {
+                best = (bound, child);
+            }
+        }
+        best.1
+    }
+
+    /// The plays of the root with their number of visits and their total reward for the player
+    /// making them, in the order they were first tried
+    pub(crate) fn root_plays(&self) -> Vec<(G::InputCoordinate, u32, f64)> {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|&child| {
+                let child = &self.nodes[child];
+                (child.play.unwrap(), child.visits, child.reward)
+            })
+            .collect()
+    }
+}
+
+/// Plays at random in `game`, where `last_player` played last, until the end of the game, and
+/// returns the winner, or None for a draw
+fn playout<G: Game>(game: &mut G, last_player: G::Player, rng: &mut impl Rng) -> Option<G::Player> {
+    let mut player = last_player;
+    loop {
+        if let Some(winner) = game.get_winner() {
+            return Some(winner);
+        }
+        if game.is_full() {
+            return None;
+        }
+        player = player.other();
+        let play = *game.possible_plays().choose(rng)?;
+        game.play(player, play)
+            .expect("the possible plays should be possible");
+    }
+}
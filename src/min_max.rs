@@ -1,20 +1,235 @@
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering::Relaxed;
-use std::sync::atomic::{AtomicBool, AtomicI32};
-use std::sync::Arc;
+use std::time::Instant;
 
-use parking_lot::Mutex;
 use rayon::iter::*;
 
+use crate::game::evaluator::Evaluator;
 use crate::game::player::Player;
 use crate::game::state::GameState::*;
 use crate::game::Game;
+use crate::min_max::cancellation::CancellationToken;
+use crate::min_max::config::SearchConfig;
 use crate::min_max::node::GameNode;
+use crate::min_max::ordering::MoveOrdering;
+use crate::min_max::outcome::Outcome;
+use crate::min_max::stats::{SearchStats, StatsRecorder};
+use crate::min_max::transposition::{Bound, Entry, TranspositionTable};
 use crate::scalar::Scalar;
 
+pub mod analysis;
+pub mod cancellation;
+pub mod config;
+pub mod make_unmake;
 pub mod node;
+mod ordering;
+pub mod outcome;
+pub mod stats;
+mod tests;
+pub mod transposition;
+
+/// A line of plays from a position, each with the weight of the position it leads to, see
+/// `GameNode::principal_variation`
+pub type Variation<G> = Vec<(<G as Game>::InputCoordinate, <G as Game>::Score)>;
+
+/// How the children of a node are explored, see `SearchConfig::with_algorithm`
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Every child is explored with the window of its parent
+    #[default]
+    AlphaBeta,
+    /// Principal variation search: the first child is explored with the window of its parent, and
+    /// the others with a null window, which only tells whether they are better than the best one
+    /// so far. The ones that are better are explored again with the full window. This cuts more
+    /// when the first child is usually the best one.
+    PrincipalVariation,
+}
+
+/// Everything that does not change during a search
+struct Search<'a, G: Game, E: Evaluator<G>> {
+    bot_player: G::Player,
+    max_depth: u32,
+    config: &'a SearchConfig<G::Score>,
+    /// number of plays that were actually made, not just predicted
+    real_plays: u32,
+    evaluator: &'a E,
+    table: &'a TranspositionTable<G>,
+    deadline: Option<Instant>,
+    cancellation: &'a CancellationToken,
+    aborted: AtomicBool,
+    stats: StatsRecorder,
+    ordering: MoveOrdering<G>,
+}
+
+impl<'a, G: Game, E: Evaluator<G>> Search<'a, G, E> {
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        bot_player: G::Player,
+        max_depth: u32,
+        config: &'a SearchConfig<G::Score>,
+        real_plays: u32,
+        evaluator: &'a E,
+        table: &'a TranspositionTable<G>,
+        deadline: Option<Instant>,
+        cancellation: &'a CancellationToken,
+    ) -> Self {
+        Self {
+            bot_player,
+            max_depth,
+            config,
+            real_plays,
+            evaluator,
+            table,
+            deadline,
+            cancellation,
+            aborted: AtomicBool::new(false),
+            stats: StatsRecorder::new(),
+            ordering: MoveOrdering::new(max_depth),
+        }
+    }
+
+    /// Returns true if the search should be aborted because the deadline is over or it was
+    /// cancelled
+    fn should_stop(&self) -> bool {
+        if self.aborted.load(Relaxed) {
+            return true;
+        }
+        if self.cancellation.is_cancelled()
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.aborted.store(true, Relaxed);
+            return true;
+        }
+        false
+    }
+
+    /// Score of a game that is not over where the exploration stops, before any depth penalty
+    fn leaf_score(&self, game: &G) -> G::Score {
+        self.stats.leaf_evaluation();
+        if self.config.use_game_score {
+            // the heuristic is kept below the mate weights: only the real wins and losses may
+            // weigh as much as them
+            let score = self.evaluator.evaluate(game, self.bot_player);
+            Outcome::Heuristic(score).encode()
+        } else {
+            G::Score::ZERO()
+        }
+    }
+
+    /// Weight of a game won by `winner` `ply` plays after the root, see `Outcome`
+    fn win_weight(&self, winner: G::Player, ply: u32) -> G::Score {
+        if winner == self.bot_player {
+            Outcome::Win(ply).encode()
+        } else {
+            Outcome::Loss(ply).encode()
+        }
+    }
+
+    fn draw_weight(&self) -> G::Score {
+        self.config.draw_weight()
+    }
+
+    /// Weight of a game where the exploration stops, `ply` plays after the root. A game that is
+    /// over weighs as much as if it was found before the end of the exploration, whatever its
+    /// score.
+    fn leaf_weight(&self, game: &G, ply: u32) -> G::Score {
+        if let Some(winner) = game.get_winner() {
+            return self.win_weight(winner, ply);
+        }
+        if game.is_full() {
+            return self.draw_weight();
+        }
+        // we want to prioritize the closest good positions
+        self.config.depth_penalty.apply(self.leaf_score(game), ply)
+    }
+
+    /// The score to store in the table for a position `ply` plays after the root: the distances
+    /// of the wins and losses are counted from the position instead of the root, and the
+    /// heuristic weights lose the depth penalty of the plays before the position, as it may be
+    /// found at another distance from the root, or by a search from another root. The draws,
+    /// which have no penalty but cannot be told apart from the heuristic weights, are only exact
+    /// when they are found at the same distance.
+    fn table_score(&self, weight: G::Score, ply: u32) -> G::Score {
+        match Outcome::decode(weight) {
+            Outcome::Heuristic(weight) => {
+                Outcome::Heuristic(self.config.depth_penalty.remove(weight, ply))
+            }
+            outcome => outcome.shift(ply as i32),
+        }
+        .encode()
+    }
+
+    /// The weight of a position `ply` plays after the root, from its score in the table, see
+    /// `table_score`
+    fn table_weight(&self, score: G::Score, ply: u32) -> G::Score {
+        match Outcome::decode(score) {
+            Outcome::Heuristic(score) => {
+                Outcome::Heuristic(self.config.depth_penalty.apply(score, ply))
+            }
+            outcome => outcome.shift(-(ply as i32)),
+        }
+        .encode()
+    }
+
+    /// Whether `entry` can replace the exploration of its position, with `remaining_depth` plays
+    /// left to explore. A deeper entry is even better, but when the search must be reproducible,
+    /// only the entries of the same depth from this search are used: they are the only ones whose
+    /// weights do not depend on which thread stored its entry first.
+    fn is_usable(&self, entry: &Entry<G>, remaining_depth: u32) -> bool {
+        if self.config.reproducible() {
+            entry.depth == remaining_depth && self.table.is_current(entry)
+        } else {
+            entry.depth >= remaining_depth
+        }
+    }
+
+    /// The lowest weight to explore a play with, for a position whose best play so far has the
+    /// weight `best`. Usually `best`, as the plays that are not better do not need their exact
+    /// weight. When the search must be reproducible, the plays of the root that are as good as
+    /// the best one also need their exact weight, so that the first of them in the order of the
+    /// possible plays can be chosen, whichever thread found its weight first.
+    fn root_alpha(&self, best: G::Score) -> G::Score {
+        if self.config.reproducible() && best > G::Score::MIN() {
+            best - G::Score::from(1)
+        } else {
+            best
+        }
+    }
+}
+
+/// The best of two children weights for the player choosing between them, with their plays. The
+/// first one is kept if they are equal, and a missing play is never the best.
+fn best_child<S: Ord, P>(first: (S, Option<P>), second: (S, Option<P>)) -> (S, Option<P>) {
+    if second.1.is_some() && (first.1.is_none() || second.0 > first.0) {
+        second
+    } else {
+        first
+    }
+}
 
 impl<G: Game> GameNode<G> {
-    pub fn explore_children(&mut self, bot_player: G::Player, max_depth: u32, real_plays: u32) {
+    /// Explores the children of this node up to `max_depth` plays ahead as configured by `config`,
+    /// scoring the positions where the exploration stops with `evaluator`. The aspiration window
+    /// (see `Aspiration`) is around the weight of this node, if it has one.
+    ///
+    /// If `deadline` is reached or `cancellation` is cancelled before the end, the search is
+    /// aborted and the returned stats are not `completed`. The tree stays usable: the nodes that
+    /// were being explored get no weight, and the other nodes keep the weight of their last
+    /// complete exploration, which may come from a previous search. These weights should not be
+    /// used to choose a play, but they are still a good hint for ordering the next search.
+    #[allow(clippy::too_many_arguments)]
+    pub fn explore_children<E: Evaluator<G>>(
+        &mut self,
+        bot_player: G::Player,
+        max_depth: u32,
+        config: &SearchConfig<G::Score>,
+        evaluator: &E,
+        table: &TranspositionTable<G>,
+        deadline: Option<Instant>,
+        cancellation: &CancellationToken,
+    ) -> SearchStats {
         let now_playing = match self.game_state {
             PlayersTurn(playing_player, _) => playing_player,
             _ => panic!(
@@ -23,176 +238,298 @@ impl<G: Game> GameNode<G> {
             ),
         };
 
-        println!("Exploring possibilities...");
-
-        #[cfg(debug_assertions)]
-        let call_count = Arc::new(AtomicI32::new(0));
+        log::info!("Exploring possibilities...");
 
-        self.explore_children_recur(
+        table.new_search();
+        let search = Search::new(
             bot_player,
             max_depth,
-            now_playing,
-            real_plays,
-            self.children.is_empty(),
-            Arc::new(Mutex::new(if now_playing == bot_player {
-                G::Score::MAX()
-            } else {
-                G::Score::MIN()
-            })),
-            #[cfg(debug_assertions)]
-            call_count.clone(),
+            config,
+            self.depth(),
+            evaluator,
+            table,
+            deadline,
+            cancellation,
         );
 
-        #[cfg(debug_assertions)]
-        {
-            let call_count = call_count.load(Relaxed) as f64;
-            let call_cout_str = if call_count >= 1e9 {
-                format!("{:.2}G", call_count / 1e9)
-            } else if call_count >= 1e6 {
-                format!("{:.2}M", call_count / 1e6)
-            } else if call_count >= 1e3 {
-                format!("{:.2}K", call_count / 1e3)
+        // the weight of the last exploration of this node, usually one play less deep
+        let guess = self.weight().map(|weight| {
+            if now_playing == bot_player {
+                weight
             } else {
-                format!("{}", call_count)
-            };
-            println!("Call count: {call_cout_str}");
-        }
+                -weight
+            }
+        });
+        let completed = config
+            .aspiration
+            .explore(guess, &search.stats, |alpha, beta| {
+                self.negamax(&search, now_playing, self.children.is_empty(), alpha, beta)
+            })
+            .is_some();
+
+        let stats = search.stats.finish(max_depth, completed);
+        log::debug!("{stats}");
+        stats
     }
 
-    const FIRST_FORK: i32 = 3;
-
-    const FORK_INTERVAL: i32 = 4;
-
-    const USE_GAME_SCORE: bool = true;
-
-    const MULTI_THREADING: bool = true;
-
-    /// Explore children recursively
+    /// Negamax search: the weights are from the point of view of `now_playing`, and the best play
+    /// is the one whose child has the lowest weight for the other player.
+    ///
+    /// The real weight is returned if it is strictly between `alpha` and `beta`. Otherwise the
+    /// returned weight is only a bound of the real one (fail-soft): the real weight is at most the
+    /// returned one if it is `alpha` or lower, and at least the returned one if it is `beta` or
+    /// higher. The weight stored in the node is always from the point of view of the bot.
     ///
     /// # Parameters
-    /// * `real_plays` - number of plays made that were actually made, not just predicted
     /// * `checks` - if true, will check if the game is won or draw, else will assume it is not
-    /// * `worst_sibling_score` - if now_playing is the bot, the minimum score to consider, because
-    ///    we are maximizing children, else the maximum score to consider because we are minimizing
-    ///    children
     ///
     /// # Returns
-    /// The weight of the node
-    fn explore_children_recur(
+    /// The weight of the node for `now_playing`, or None if the search was aborted
+    fn negamax<E: Evaluator<G>>(
         &mut self,
-        bot_player: G::Player,
-        max_depth: u32,
+        search: &Search<G, E>,
         now_playing: G::Player,
-        real_plays: u32,
         checks: bool,
-        worst_sibling_score: Arc<Mutex<G::Score>>,
-        #[cfg(debug_assertions)] call_count: Arc<AtomicI32>,
-    ) -> G::Score {
+        mut alpha: G::Score,
+        mut beta: G::Score,
+    ) -> Option<G::Score> {
+        let real_plays = search.real_plays;
         debug_assert!(self.depth() >= real_plays, "Negative exploration");
 
-        #[cfg(debug_assertions)]
-        call_count.fetch_add(1, Relaxed);
+        search.stats.node(self.depth() - real_plays);
+
+        let maximize = now_playing == search.bot_player;
+        // converts between the weights of the bot and the ones of now_playing, both ways
+        let for_now_playing = |weight: G::Score| if maximize { weight } else { -weight };
+        // the bounds of the bot that are lower and upper bounds for now_playing
+        let (lower_bound, upper_bound) = if maximize {
+            (Bound::Lower, Bound::Upper)
+        } else {
+            (Bound::Upper, Bound::Lower)
+        };
 
         let do_checks = checks || self.children.is_empty();
 
-        if self.check_max_depth(bot_player, max_depth, real_plays)
-            || (do_checks && (self.check_winner(bot_player) || self.check_draw()))
+        if self.check_max_depth(search)
+            || (do_checks && (self.check_winner(search) || self.check_draw(search)))
         {
             // weight should have been set by the functions above
-            return self.weight().unwrap();
+            return self.weight().map(for_now_playing);
         }
 
-        let maximize = now_playing == bot_player;
+        if search.should_stop() {
+            return None;
+        }
 
-        let worst_child_score = Arc::new(Mutex::new(if !maximize {
-            // inverting for children
-            G::Score::MAX()
-        } else {
-            G::Score::MIN()
-        }));
+        let key = self.game.hash_key();
+        let ply = self.depth() - real_plays;
+        let remaining_depth = search.max_depth - ply;
+        let entry = search.table.get(key);
+        // the root needs its children to be explored, so the table is only used below it
+        if ply > 0 {
+            if let Some(entry) = entry {
+                if search.is_usable(&entry, remaining_depth) {
+                    let entry_weight = search.table_weight(entry.score, ply);
+                    let score = for_now_playing(entry_weight);
+                    if entry.bound == Bound::Exact {
+                        search.stats.table_hit();
+                        self.set_weight(Some(entry_weight));
+                        return Some(score);
+                    } else if entry.bound == lower_bound {
+                        alpha = alpha.max(score);
+                    } else {
+                        beta = beta.min(score);
+                    }
+                    if alpha >= beta {
+                        search.stats.table_hit();
+                        self.set_weight(Some(entry_weight));
+                        return Some(score);
+                    }
+                }
+            }
+        }
+        let (original_alpha, original_beta) = (alpha, beta);
+
+        let check_children = self.fill_children_and_destroy_game(search, now_playing, maximize);
+        if check_children {
+            // new children, without weights from a previous search to sort them
+            let hash_play = entry.and_then(|entry| entry.best_play);
+            search
+                .ordering
+                .sort(&mut self.children, |(play, _)| *play, ply, hash_play);
+        }
 
-        // WARNING: (maybe) destroying game here, for memory efficiency
+        let Some((best, best_play)) =
+            self.negamax_children(search, now_playing, check_children, alpha, beta)
+        else {
+            self.set_weight(None);
+            return None;
+        };
 
-        let check_children = self.fill_children_and_destroy_game(now_playing);
+        let weight = for_now_playing(best);
+        self.set_weight(Some(weight));
+        if ply == 0 && search.config.reproducible() {
+            // best_play chooses the first of the plays as good as the best one, see root_alpha
+            let order = search.config.play_order.possible_plays(&self.game);
+            self.children
+                .sort_by_key(|(play, _)| order.iter().position(|other| other == play));
+        }
 
-        let auto_destroy = AtomicBool::new(false);
+        let bound = if best <= original_alpha {
+            upper_bound
+        } else if best >= original_beta {
+            lower_bound
+        } else {
+            Bound::Exact
+        };
+        // when all the children were cut, none of them is known to be the best
+        let best_play = best_play.filter(|_| bound != upper_bound);
+        search.table.store(
+            key,
+            remaining_depth,
+            search.table_score(weight, ply),
+            bound,
+            best_play,
+        );
 
-        let maybe_explore_children = |child: &mut Self| {
-            let child_score = child.explore_children_recur(
-                bot_player,
-                max_depth,
-                now_playing.other(),
-                real_plays,
-                check_children,
-                worst_child_score.clone(),
-                #[cfg(debug_assertions)]
-                call_count.clone(),
-            );
-            let mut worst_child_score = worst_child_score.lock();
-            // println!("maximize: {maximize},  child: {child_score} worst child: {worst_child_score}");
-
-            if (maximize && child_score > *worst_child_score)
-                || (!maximize && child_score < *worst_child_score)
-            {
-                *worst_child_score = child_score;
-            }
-            let parent_maximize = !maximize;
-            let worst_sibling_score = worst_sibling_score.lock();
-
-            // if the parent will not choose us
-            if (parent_maximize && *worst_child_score < *worst_sibling_score)
-                || (!parent_maximize && *worst_child_score > *worst_sibling_score)
-            {
-                auto_destroy.store(true, Relaxed);
-                None // abort checking childs
+        // a weight of MIN or MAX cannot be a bound, as there is nothing below or above it
+        if bound != Bound::Exact && best != G::Score::MIN() && best != G::Score::MAX() {
+            // the children only have bounds, which would be wrong hints for best_play
+            self.children = Vec::with_capacity(0);
+        }
+
+        Some(best)
+    }
+
+    /// Explores the children of a node for `negamax`, and returns the weight of the best one for
+    /// `now_playing` and its play, or None if the search was aborted.
+    ///
+    /// The first child is explored alone, so that the others can be cut by its weight. Then, at
+    /// the nodes that are split (see `SearchConfig::splits`), the others are explored in parallel with the same
+    /// window, and a cutoff found by one of them stops the ones that did not start yet.
+    fn negamax_children<E: Evaluator<G>>(
+        &mut self,
+        search: &Search<G, E>,
+        now_playing: G::Player,
+        check_children: bool,
+        mut alpha: G::Score,
+        beta: G::Score,
+    ) -> Option<(G::Score, Option<G::InputCoordinate>)> {
+        let ply = self.depth() - search.real_plays;
+        let remaining_depth = search.max_depth - ply;
+        // the window of the children, which is only different at the root, see root_alpha
+        let child_alpha = |alpha| {
+            if ply == 0 {
+                search.root_alpha(alpha)
             } else {
-                // parent may choose us
-                Some(()) // continue checking children
+                alpha
             }
         };
-
-        //let spaces = "| ".repeat(self.depth() as usize);
-        //println!("{}({})Exploring {} children of depth {} (actual: {})...", spaces, self.id(), self.children.len(), self.depth(), self.depth().overflowing_sub(real_plays).0);
-
-        //println!("({} - {real_plays} = {} )", self.depth(), self.depth().overflowing_sub(real_plays).0);
-        if self.is_parallelize_depth(real_plays) {
-            // parallelize
-            self.children.par_iter_mut().try_for_each(|(_, child)| {
-                //print!("F");
-                maybe_explore_children(child)
-            });
+        let mut best = (G::Score::MIN(), None);
+        let sequential = if search.config.splits(remaining_depth) {
+            1.min(self.children.len())
         } else {
-            self.children
-                .iter_mut()
-                .try_for_each(|(_, child)| maybe_explore_children(child));
+            self.children.len()
         };
-        let weight = (*worst_child_score.lock()).into();
-        self.set_weight(weight);
-
-        if auto_destroy.load(Relaxed) {
-            self.children = Vec::with_capacity(0);
+        let (sequential_children, parallel_children) = self.children.split_at_mut(sequential);
+        for (index, (play, child)) in sequential_children.iter_mut().enumerate() {
+            let weight = -child.negamax_child(
+                search,
+                now_playing,
+                check_children,
+                child_alpha(alpha),
+                beta,
+                index == 0,
+            )?;
+            best = best_child(best, (weight, Some(*play)));
+            alpha = alpha.max(best.0);
+            if child_alpha(alpha) >= beta {
+                search.stats.cutoff();
+                search.ordering.cutoff(*play, ply, remaining_depth);
+                return Some(best);
+            }
         }
 
-        weight.unwrap()
+        let cut = AtomicBool::new(false);
+        let parallel_best = parallel_children
+            .par_iter_mut()
+            .map(|(play, child)| {
+                if cut.load(Relaxed) {
+                    // the node is already too good to be chosen by the opponent
+                    return Some((G::Score::MIN(), None));
+                }
+                let weight = -child.negamax_child(
+                    search,
+                    now_playing,
+                    check_children,
+                    child_alpha(alpha),
+                    beta,
+                    false,
+                )?;
+                if child_alpha(weight) >= beta {
+                    cut.store(true, Relaxed);
+                }
+                Some((weight, Some(*play)))
+            })
+            .try_reduce(|| (G::Score::MIN(), None), |a, b| Some(best_child(a, b)))?;
+        best = best_child(best, parallel_best);
+        if best.0 >= beta {
+            search.stats.cutoff();
+            if let Some(play) = best.1 {
+                search.ordering.cutoff(play, ply, remaining_depth);
+            }
+        }
+        Some(best)
     }
 
-    fn is_parallelize_depth(&self, real_plays: u32) -> bool {
-        let depth = self.depth() as i32 - real_plays as i32;
-        (depth - Self::FIRST_FORK) % Self::FORK_INTERVAL == 0 && Self::MULTI_THREADING
+    /// Explores a child for `negamax_children`, with the window of the parent. With PVS, the
+    /// children after the first one are explored with a null window first, see `Algorithm`.
+    ///
+    /// Returns the weight of the child for the player playing it, like `negamax`.
+    fn negamax_child<E: Evaluator<G>>(
+        &mut self,
+        search: &Search<G, E>,
+        parent_playing: G::Player,
+        checks: bool,
+        alpha: G::Score,
+        beta: G::Score,
+        first: bool,
+    ) -> Option<G::Score> {
+        let now_playing = parent_playing.other();
+        if first || search.config.algorithm == Algorithm::AlphaBeta {
+            return self.negamax(search, now_playing, checks, -beta, -alpha);
+        }
+        let weight = -self.negamax(
+            search,
+            now_playing,
+            checks,
+            -alpha - G::Score::from(1),
+            -alpha,
+        )?;
+        if weight > alpha && weight < beta {
+            // better than the best child so far, its real weight is needed
+            search.stats.research();
+            return self.negamax(search, now_playing, checks, -beta, -alpha);
+        }
+        Some(-weight)
     }
 
-    /// Returns true if childrens should be checked for win or draw, false if they were already checked
-    fn fill_children_and_destroy_game(&mut self, now_playing: <G as Game>::Player) -> bool {
+    /// Returns true if childrens should be checked for win or draw, false if they were already checked.
+    /// Children that were already there are sorted by their weight from the last search, best
+    /// first, as they are likely to be the best again and to cut more branches.
+    fn fill_children_and_destroy_game<E: Evaluator<G>>(
+        &mut self,
+        search: &Search<G, E>,
+        now_playing: <G as Game>::Player,
+        maximize: bool,
+    ) -> bool {
         if self.children.is_empty() {
             let game = &self.game;
-            // if we take the game from us, we don't have to clone it, but we can do this only once
-
-            let possible_plays = game.possible_plays();
+            let possible_plays = search.config.play_order.possible_plays(game);
             let possibilities = possible_plays.len();
             let mut vec = Vec::with_capacity(possibilities);
 
-            for i in 0..(possibilities) {
-                let input_coord = possible_plays[i];
+            for &input_coord in possible_plays.iter() {
                 let mut game = game.clone();
                 game.play(now_playing, input_coord).unwrap(); // should not panic as input_coord is a possible play
                 vec.push((
@@ -205,159 +542,102 @@ impl<G: Game> GameNode<G> {
                     ),
                 ));
             }
-            /*if possibilities >= 1 {
-                let input_coord = possible_plays[possibilities - 1];
-                let mut game = if take_game {
-                    // we don't want to destroy the game in the root node
-                    // add the game itself while "destroying" it for us
-                    self.game.take().unwrap()
-                } else {
-                    game.clone()
-                };
-                game.play(now_playing, input_coord).unwrap();
-                vec.push((
-                    input_coord,
-                    GameNode::new(
-                        Some(game),
-                        self.depth() + 1,
-                        None,
-                        PlayersTurn(now_playing.other(), Some(input_coord)),
-                    ),
-                ));
-            }*/
             self.children = vec;
             true
         } else {
-            //self.regenerate_children_games();
+            if maximize {
+                self.children
+                    .sort_by_key(|(_, child)| std::cmp::Reverse(child.weight()));
+            } else {
+                self.children
+                    .sort_by_key(|(_, child)| (child.weight().is_none(), child.weight()));
+            }
             false
         }
     }
 
-    fn check_draw(&mut self) -> bool {
-        // consider draw as a loss for the bot, but not a loss as important as a real loss
-        let half_loose = G::Score::MIN().div(2);
+    fn check_draw<E: Evaluator<G>>(&mut self, search: &Search<G, E>) -> bool {
+        let draw_weight = search.draw_weight();
         if let Draw(_, _) = self.game_state {
             // if we are here, it means that this function was called twice on the same node
             // this is due to multi-threading
-            self.set_weight(Some(half_loose));
+            self.set_weight(Some(draw_weight));
             return true;
         }
         if self.game.is_full() {
-            self.set_weight(Some(half_loose));
+            self.set_weight(Some(draw_weight));
             self.game_state = self.game_state.to_draw();
             return true;
         }
         false
     }
 
-    fn check_winner(&mut self, bot_player: <G as Game>::Player) -> bool {
+    fn check_winner<E: Evaluator<G>>(&mut self, search: &Search<G, E>) -> bool {
         if let WonBy(winner, _) = self.game_state {
-            self.set_weight(Self::win_weight(winner, bot_player));
+            self.set_weight(Some(
+                search.win_weight(winner, self.depth() - search.real_plays),
+            ));
             return true;
         }
         let winner = self.game.get_winner();
         if let Some(winner) = winner {
-            self.set_weight(Self::win_weight(winner, bot_player));
+            self.set_weight(Some(
+                search.win_weight(winner, self.depth() - search.real_plays),
+            ));
             self.game_state = self.game_state.to_win();
             return true;
         }
         false
     }
 
-    fn win_weight(
-        winner: <G as Game>::Player,
-        bot_player: <G as Game>::Player,
-    ) -> Option<<G as Game>::Score> {
-        Some(if winner == bot_player {
-            G::Score::MAX()
-        } else {
-            G::Score::MIN()
-        })
-    }
-
-    //noinspection RsConstantConditionIf
-    fn check_max_depth(
-        &mut self,
-        bot_player: <G as Game>::Player,
-        max_depth: u32,
-        real_plays: u32,
-    ) -> bool {
-        if self.depth() >= max_depth + real_plays {
-            let game = &self.game;
-            let weight = Some(
-                if Self::USE_GAME_SCORE {
-                    // I know this is a constant, but this allows me to change it easily
-                    let score = game.get_score(bot_player); // computing score here
-                    if score == G::Score::MAX() {
-                        self.game_state = self.game_state.to_win_by(bot_player);
-                    } else if score == G::Score::MIN() {
-                        self.game_state = self.game_state.to_win_by(bot_player.other());
-                    }
-                    score
-                } else {
-                    if let Some(winner) = game.get_winner() {
-                        self.game_state = self.game_state.to_win_by(winner);
-                        if winner == bot_player {
-                            G::Score::MAX()
-                        } else {
-                            G::Score::MIN()
-                        }
-                    } else {
-                        G::Score::ZERO()
-                    }
+    fn check_max_depth<E: Evaluator<G>>(&mut self, search: &Search<G, E>) -> bool {
+        let real_plays = search.real_plays;
+        if self.depth() >= search.max_depth + real_plays {
+            // the state may already be known if the node was explored by a previous search
+            if let PlayersTurn(..) = self.game_state {
+                if let Some(winner) = self.game.get_winner() {
+                    self.game_state = self.game_state.to_win_by(winner);
+                } else if self.game.is_full() {
+                    self.game_state = self.game_state.to_draw();
                 }
-                .add_towards_0((self.depth() - real_plays) as i32), // we want to prioritize the fastest win
-            );
-            self.set_weight(weight);
+            }
+            let weight = search.leaf_weight(&self.game, self.depth() - real_plays);
+            self.set_weight(Some(weight));
             return true;
         }
         false
     }
 
-    pub fn into_best_child(self) -> Self {
-        let target_weight = self.weight().unwrap();
-        let mut best_child = None;
-        for (_, child) in self.children.into_iter() {
-            if child.weight().unwrap() == target_weight {
-                best_child = Some(child);
+    /// The play leading to the child with the same weight as this node, i.e. the play chosen by
+    /// the last exploration, and its weight. None if the node was not explored.
+    pub fn best_play(&self) -> Option<(G::InputCoordinate, G::Score)> {
+        let target_weight = self.weight()?;
+        self.children
+            .iter()
+            .find(|(_, child)| child.weight() == Some(target_weight))
+            .map(|(play, _)| (*play, target_weight))
+    }
+
+    /// The line of plays expected by the last exploration of `max_depth` plays: the best play of
+    /// this node, then the best answer to it, and so on, each with the weight of the node it leads
+    /// to. The line ends where that exploration stopped, or earlier at the nodes whose weight was
+    /// found in the transposition table. Empty if the node was not explored.
+    pub fn principal_variation(&self, max_depth: u32) -> Variation<G> {
+        let mut variation = Vec::new();
+        let mut node = self;
+        // deeper children may remain from a previous, deeper search
+        while node.depth() < self.depth() + max_depth {
+            let Some((play, weight)) = node.best_play() else {
                 break;
-            }
+            };
+            variation.push((play, weight));
+            node = node
+                .children
+                .iter()
+                .find(|(child_play, _)| *child_play == play)
+                .map(|(_, child)| child)
+                .unwrap(); // the child exists as best_play found it
         }
-        let best_child = best_child.expect("No children found");
-        best_child
-        /*
-                let mut best = G::Score::MIN();
-                let mut best_child = None;
-                for mut child in self.children.into_values() {
-                    let child_weight = child.weight().unwrap();
-                    if child_weight == target_weight {
-                        child.fill_play(self.game.unwrap());
-                        return child;
-                    }
-                    if child_weight > best {
-                        best = child_weight;
-                        best_child = Some(child);
-                    }
-                }
-                println!("Could not find target weight {} in children", target_weight);
-                let mut best_child = best_child.expect("No children found");
-                best_child.fill_play(self.game.unwrap());
-                best_child
-        */
-        /*
-        let game = self.game
-            .expect("game should not have been removed from the root node");
-
-        let mut best_child = self
-            .children // parallelization here slows downs the program a lot
-            .into_iter()
-            .max_by_key(|(_, child)| child.weight())
-            .unwrap()
-            .1;
-
-        best_child.fill_play(game);
-        best_child
-
-         */
+        variation
     }
 }
@@ -0,0 +1,126 @@
+use std::cmp::Reverse;
+use std::time::Instant;
+
+use rayon::iter::*;
+
+use crate::game::evaluator::Evaluator;
+use crate::game::player::Player;
+use crate::game::state::GameState::PlayersTurn;
+use crate::game::Game;
+use crate::min_max::cancellation::CancellationToken;
+use crate::min_max::config::SearchConfig;
+use crate::min_max::node::GameNode;
+use crate::min_max::stats::SearchStats;
+use crate::min_max::transposition::TranspositionTable;
+use crate::min_max::{Search, Variation};
+use crate::scalar::Scalar;
+
+/// The exact weight of a play, as found by `GameNode::analyze` or `make_unmake::analyze`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalyzedPlay<G: Game> {
+    pub play: G::InputCoordinate,
+    pub weight: G::Score,
+    /// The plays expected after this one, starting with it, see `GameNode::principal_variation`
+    pub variation: Variation<G>,
+}
+
+/// Sorts the plays from the best to the worst for the player choosing between them
+pub(super) fn sort_analysis<G: Game>(analysis: &mut [AnalyzedPlay<G>], maximize: bool) {
+    if maximize {
+        analysis.sort_by_key(|analyzed| Reverse(analyzed.weight));
+    } else {
+        analysis.sort_by_key(|analyzed| analyzed.weight);
+    }
+}
+
+impl<G: Game> GameNode<G> {
+    /// Like `explore_children`, but every child is explored with a full window instead of being
+    /// cut by the weights of its siblings, so that all the plays get an exact weight and not only
+    /// the best one. This is slower, as only the cutoffs below each child remain.
+    ///
+    /// Returns the plays sorted from the best to the worst for the player who plays next, or None
+    /// if the search was aborted, and the stats of the search. The weight of this node is set to
+    /// the weight of the best play.
+    #[allow(clippy::too_many_arguments)]
+    pub fn analyze<E: Evaluator<G>>(
+        &mut self,
+        bot_player: G::Player,
+        max_depth: u32,
+        config: &SearchConfig<G::Score>,
+        evaluator: &E,
+        table: &TranspositionTable<G>,
+        deadline: Option<Instant>,
+        cancellation: &CancellationToken,
+    ) -> (Option<Vec<AnalyzedPlay<G>>>, SearchStats) {
+        let now_playing = match self.game_state {
+            PlayersTurn(playing_player, _) => playing_player,
+            _ => panic!(
+                "Cannot analyze a node that is not starting or played by a player. Current state: {}",
+                self.game_state
+            ),
+        };
+
+        log::info!("Analyzing possibilities...");
+
+        table.new_search();
+        let search = Search::new(
+            bot_player,
+            max_depth,
+            config,
+            self.depth(),
+            evaluator,
+            table,
+            deadline,
+            cancellation,
+        );
+        search.stats.node(0);
+
+        if self.check_winner(&search) || self.check_draw(&search) {
+            // nothing left to play
+            let stats = search.stats.finish(max_depth, true);
+            return (Some(Vec::new()), stats);
+        }
+
+        let maximize = now_playing == bot_player;
+        let check_children = self.fill_children_and_destroy_game(&search, now_playing, maximize);
+        let analyze_child = |(play, child): &mut (G::InputCoordinate, Self)| {
+            // each child gets a full window, so that its siblings cannot cut it
+            let weight = -child.negamax(
+                &search,
+                now_playing.other(),
+                check_children,
+                G::Score::MIN(),
+                G::Score::MAX(),
+            )?;
+            // back to the point of view of the bot
+            let weight = if maximize { weight } else { -weight };
+            let mut variation = vec![(*play, weight)];
+            variation.extend(child.principal_variation(max_depth - 1));
+            Some(AnalyzedPlay {
+                play: *play,
+                weight,
+                variation,
+            })
+        };
+        let analysis: Option<Vec<_>> = if config.multi_threading {
+            self.children.par_iter_mut().map(analyze_child).collect()
+        } else {
+            self.children.iter_mut().map(analyze_child).collect()
+        };
+
+        let analysis = analysis.map(|mut analysis| {
+            sort_analysis(&mut analysis, maximize);
+            analysis
+        });
+        self.set_weight(
+            analysis
+                .as_ref()
+                .and_then(|analysis| analysis.first())
+                .map(|best| best.weight),
+        );
+
+        let stats = search.stats.finish(max_depth, analysis.is_some());
+        log::debug!("{stats}");
+        (analysis, stats)
+    }
+}
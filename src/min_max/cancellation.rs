@@ -0,0 +1,31 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+
+/// A flag shared between a search and other threads, allowing them to stop the search early.
+///
+/// Cloning the token gives another handle on the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the searches using this token to stop as soon as possible
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Relaxed)
+    }
+
+    /// Allows the token to be used for a new search
+    pub fn reset(&self) {
+        self.cancelled.store(false, Relaxed);
+    }
+}
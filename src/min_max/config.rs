@@ -0,0 +1,305 @@
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::game::Game;
+use crate::min_max::outcome::Outcome;
+use crate::min_max::stats::StatsRecorder;
+use crate::min_max::Algorithm;
+use crate::scalar::Scalar;
+
+/// How a search scores and explores the positions, see `GameNode::explore_children`. Built from
+/// the default one with the `with_*` methods:
+///
+/// ```
+/// use min_max::min_max::config::{PlayOrder, SearchConfig};
+/// use min_max::min_max::Algorithm;
+///
+/// let config: SearchConfig<i32> = SearchConfig::default()
+///     .with_algorithm(Algorithm::PrincipalVariation)
+///     .with_contempt(0)
+///     .with_play_order(PlayOrder::Fixed);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SearchConfig<S: Scalar> {
+    pub(crate) algorithm: Algorithm,
+    pub(crate) use_game_score: bool,
+    pub(crate) contempt: S,
+    pub(crate) depth_penalty: DepthPenalty,
+    pub(crate) play_order: PlayOrder,
+    pub(crate) multi_threading: bool,
+    pub(crate) min_split_depth: u32,
+    pub(crate) aspiration: Aspiration,
+    pub(crate) endgame_plays: u32,
+    pub(crate) endgame_time: Duration,
+}
+
+impl<S: Scalar> Default for SearchConfig<S> {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::default(),
+            use_game_score: true,
+            // consider draw as a loss for the bot, but not a loss as important as a real loss
+            contempt: S::MAX().div(2),
+            depth_penalty: DepthPenalty::PerPly(1),
+            play_order: PlayOrder::Random,
+            multi_threading: true,
+            min_split_depth: 4,
+            aspiration: Aspiration::Window {
+                initial_width: 100,
+                growth: 4,
+            },
+            endgame_plays: 18,
+            endgame_time: Duration::from_secs(1),
+        }
+    }
+}
+
+impl<S: Scalar> SearchConfig<S> {
+    /// Explores the children of the positions with `algorithm`, plain alpha-beta by default
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Whether the positions where the exploration stops are scored by the evaluator (the
+    /// default), or only by their winner: 0 if the game is not won yet
+    pub fn with_game_score(mut self, use_game_score: bool) -> Self {
+        self.use_game_score = use_game_score;
+        self
+    }
+
+    /// How much the bot dislikes draws: a draw weighs `-contempt` for the bot, wherever it is
+    /// found. A positive contempt makes the bot take risks to avoid draws, which is good against
+    /// weaker opponents, a negative one makes it seek draws against stronger ones, and 0 gives the
+    /// real value of the positions, e.g. to solve them. Half of a win by default.
+    pub fn with_contempt(mut self, contempt: S) -> Self {
+        self.contempt = contempt;
+        self
+    }
+
+    /// The weight of a draw for the bot, see `with_contempt`. It stays between the weights of
+    /// the wins and losses, see `Outcome`.
+    pub fn draw_weight(&self) -> S {
+        Outcome::Heuristic(-self.contempt).encode()
+    }
+
+    /// How the weights of the positions depend on how far they are, see `DepthPenalty`
+    pub fn with_depth_penalty(mut self, depth_penalty: DepthPenalty) -> Self {
+        self.depth_penalty = depth_penalty;
+        self
+    }
+
+    /// How the plays that the game considers as good as each other are ordered, see `PlayOrder`
+    pub fn with_play_order(mut self, play_order: PlayOrder) -> Self {
+        self.play_order = play_order;
+        self
+    }
+
+    /// Shortcut for `with_play_order(PlayOrder::Seeded(seed))`: the searches become reproducible,
+    /// see `reproducible`
+    pub fn with_seed(self, seed: u64) -> Self {
+        self.with_play_order(PlayOrder::Seeded(seed))
+    }
+
+    /// Whether the searches choose the same plays each time they are given the same positions,
+    /// which is the case unless the play order is random. This holds with any number of threads,
+    /// but not with time limits, as the depth then depends on the speed of the search.
+    pub fn reproducible(&self) -> bool {
+        self.play_order != PlayOrder::Random
+    }
+
+    /// Whether the search may use several threads, true by default. The number of threads is the
+    /// one of the current rayon pool, see `Bot::with_threads`.
+    pub fn with_multi_threading(mut self, multi_threading: bool) -> Self {
+        self.multi_threading = multi_threading;
+        self
+    }
+
+    /// Young brothers wait: the younger children of a position are only explored in parallel
+    /// once its first child is explored, and only if there are at least `min_split_depth` plays
+    /// left to explore below it, as the smaller explorations are faster than sharing them between
+    /// threads. 4 by default.
+    pub fn with_min_split_depth(mut self, min_split_depth: u32) -> Self {
+        self.min_split_depth = min_split_depth;
+        self
+    }
+
+    /// How each exploration narrows its window around the last weight found for its root, see
+    /// `Aspiration`. A window of 100 on each side of it, 4 times wider after each failure, by
+    /// default.
+    pub fn with_aspiration(mut self, aspiration: Aspiration) -> Self {
+        self.aspiration = aspiration;
+        self
+    }
+
+    /// When at most `endgame_plays` plays are left before the end of the game (see
+    /// `Game::remaining_plays`), the bot explores until the end instead of stopping at its depth
+    /// limit, to find the exact result of the game: a win, a loss or a draw (see `Bot::solution`).
+    /// 0 to always stop at the limit. 18 by default, which Connect Four solves in a fraction of a
+    /// second.
+    ///
+    /// With a move time or a clock, the exploration still stops when the time is up, keeping the
+    /// best play found so far. With `SearchLimit::Depth`, the depth is explored first, and then
+    /// the end of the game for at most `with_endgame_time`.
+    pub fn with_endgame_plays(mut self, endgame_plays: u32) -> Self {
+        self.endgame_plays = endgame_plays;
+        self
+    }
+
+    /// The number of plays left from which the bot explores until the end of the game, see
+    /// `with_endgame_plays`
+    pub fn endgame_plays(&self) -> u32 {
+        self.endgame_plays
+    }
+
+    /// With `SearchLimit::Depth`, how long the bot may explore past its depth to reach the end of
+    /// the game (see `with_endgame_plays`). If the end is not reached in time, the bot plays the
+    /// best play of its depth, so the searches stay reproducible unless the end is reached just
+    /// around this time. 1 second by default.
+    pub fn with_endgame_time(mut self, endgame_time: Duration) -> Self {
+        self.endgame_time = endgame_time;
+        self
+    }
+
+    /// How long the bot may explore past its depth, see `with_endgame_time`
+    pub fn endgame_time(&self) -> Duration {
+        self.endgame_time
+    }
+
+    /// Whether the younger children of a position with `remaining_depth` plays left to explore
+    /// are explored in parallel, see `with_min_split_depth`
+    pub(crate) fn splits(&self, remaining_depth: u32) -> bool {
+        self.multi_threading
+            && remaining_depth >= self.min_split_depth
+            && rayon::current_num_threads() > 1
+    }
+}
+
+/// How the weight of a position where the exploration stops depends on its distance from the root
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DepthPenalty {
+    /// The weights do not depend on the distance
+    None,
+    /// The weights are moved towards 0 by this much for each play from the root, so that the bot
+    /// prefers the closest good positions and the farthest bad ones
+    PerPly(i32),
+}
+
+impl DepthPenalty {
+    /// The weight of a position `ply` plays after the root, whose score is `score`
+    pub(crate) fn apply<S: Scalar>(self, score: S, ply: u32) -> S {
+        match self {
+            DepthPenalty::None => score,
+            DepthPenalty::PerPly(penalty) => score.add_towards_0(penalty * ply as i32),
+        }
+    }
+
+    /// The reverse of `apply`: the score of a position `ply` plays after the root, whose weight is
+    /// `weight`. The scores that `apply` brought to 0 stay 0.
+    pub(crate) fn remove<S: Scalar>(self, weight: S, ply: u32) -> S {
+        match self {
+            DepthPenalty::None => weight,
+            DepthPenalty::PerPly(penalty) => weight.add_towards_0(-penalty * ply as i32),
+        }
+    }
+}
+
+/// Aspiration windows: with iterative deepening, the weight of the root is usually close to the one
+/// found by the previous exploration, so each exploration starts with a narrow window around it,
+/// which cuts more. When the real weight is outside of the window (the exploration fails low or
+/// high), the root is explored again with a wider window. The weights found are the same as with
+/// the full window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Aspiration {
+    /// Every exploration uses the full window
+    None,
+    /// The window starts `initial_width` below and above the last weight of the root. After each
+    /// failure, the side that failed moves `growth` times further than before from the bound of
+    /// the weight found, until it reaches `MIN` or `MAX`. Both should be at least 1.
+    Window { initial_width: i32, growth: i32 },
+}
+
+impl Aspiration {
+    /// Explores a root whose last weight was `guess`, if any, with `explore(alpha, beta)`, which
+    /// returns a weight like `GameNode::negamax`, or None if the search was aborted. Each
+    /// exploration after the first one is recorded in `stats`.
+    pub(crate) fn explore<S: Scalar>(
+        self,
+        guess: Option<S>,
+        stats: &StatsRecorder,
+        mut explore: impl FnMut(S, S) -> Option<S>,
+    ) -> Option<S> {
+        let (
+            Aspiration::Window {
+                initial_width,
+                growth,
+            },
+            Some(guess),
+        ) = (self, guess)
+        else {
+            return explore(S::MIN(), S::MAX());
+        };
+        let mut width = initial_width;
+        let (mut alpha, mut beta) = (below(guess, width), above(guess, width));
+        loop {
+            let weight = explore(alpha, beta)?;
+            width = width.saturating_mul(growth);
+            // with fail-soft, the weight is a bound of the real one, beyond the window
+            if weight <= alpha && alpha > S::MIN() {
+                alpha = below(weight, width);
+            } else if weight >= beta && beta < S::MAX() {
+                beta = above(weight, width);
+            } else {
+                return Some(weight);
+            }
+            stats.aspiration_research();
+        }
+    }
+}
+
+/// `weight - width`, or `MIN` if it would be lower
+fn below<S: Scalar>(weight: S, width: i32) -> S {
+    if S::MIN() + S::from(width) < weight {
+        weight - S::from(width)
+    } else {
+        S::MIN()
+    }
+}
+
+/// `weight + width`, or `MAX` if it would be higher
+fn above<S: Scalar>(weight: S, width: i32) -> S {
+    if weight < S::MAX() - S::from(width) {
+        weight + S::from(width)
+    } else {
+        S::MAX()
+    }
+}
+
+/// How the plays that the game considers as good as each other are ordered, see
+/// `Game::shuffle_plays`. The order changes which of equally good plays is chosen, and how fast
+/// the search is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlayOrder {
+    /// The order of `Game::possible_plays`
+    Fixed,
+    /// A new random order each time
+    Random,
+    /// An order that only depends on the seed and on the position
+    Seeded(u64),
+}
+
+impl PlayOrder {
+    /// The possible plays of `game`, in this order
+    pub(crate) fn possible_plays<G: Game>(self, game: &G) -> Vec<G::InputCoordinate> {
+        let mut plays = game.possible_plays();
+        let random = match self {
+            PlayOrder::Fixed => return plays,
+            PlayOrder::Random => rand::thread_rng().gen(),
+            PlayOrder::Seeded(seed) => StdRng::seed_from_u64(seed ^ game.hash_key()).gen(),
+        };
+        game.shuffle_plays(&mut plays, random);
+        plays
+    }
+}
@@ -0,0 +1,447 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
+use std::time::Instant;
+
+use parking_lot::Mutex;
+use rayon::iter::*;
+
+use crate::game::evaluator::Evaluator;
+use crate::game::player::Player;
+use crate::game::Game;
+use crate::min_max::analysis::{sort_analysis, AnalyzedPlay};
+use crate::min_max::cancellation::CancellationToken;
+use crate::min_max::config::SearchConfig;
+use crate::min_max::stats::SearchStats;
+use crate::min_max::transposition::{Bound, TranspositionTable};
+use crate::min_max::{best_child, Algorithm, Search, Variation};
+use crate::scalar::Scalar;
+
+/// Searches the best play for `bot_player`, who must be the next one to play in `game`.
+///
+/// Unlike `GameNode::explore_children`, no tree is built: each thread plays and undoes the plays
+/// on its own copy of the game, and only keeps the scores it needs. This uses much less memory,
+/// but nothing is remembered for the next search except what is in `table`. The aspiration window
+/// (see `Aspiration`) is around the exact weight of `game` in `table`, if it has one.
+///
+/// Returns the principal variation, starting with the best play, with the same weights as
+/// `GameNode::explore_children` (see `GameNode::principal_variation`), or None if the search was
+/// aborted (see `GameNode::explore_children`), and the stats of the search.
+#[allow(clippy::too_many_arguments)]
+pub fn best_play<G: Game, E: Evaluator<G>>(
+    game: &G,
+    bot_player: G::Player,
+    max_depth: u32,
+    config: &SearchConfig<G::Score>,
+    evaluator: &E,
+    table: &TranspositionTable<G>,
+    deadline: Option<Instant>,
+    cancellation: &CancellationToken,
+) -> (Option<Variation<G>>, SearchStats) {
+    table.new_search();
+    let search = Search::new(
+        bot_player,
+        max_depth,
+        config,
+        0,
+        evaluator,
+        table,
+        deadline,
+        cancellation,
+    );
+
+    let possible_plays = config.play_order.possible_plays(game);
+    // the first of the plays as good as the best one comes first here, see Search::root_alpha
+    let rank = |play: G::InputCoordinate| possible_plays.iter().position(|other| *other == play);
+    // the weight of the best play so far, and the best play followed by the plays expected after it
+    let best = Mutex::new(None::<(G::Score, Vec<G::InputCoordinate>)>);
+    // whether a play reached beta, so that the other ones are not needed
+    let cut = AtomicBool::new(false);
+    let explore_play = |play: G::InputCoordinate, first: bool, window_alpha, beta| {
+        if cut.load(Relaxed) {
+            return Some(());
+        }
+        let mut game = game.clone();
+        game.play(bot_player, play).unwrap(); // should not panic as play is a possible play
+
+        // plays that are not better than the best one so far don't need an exact score
+        let alpha = search.root_alpha(
+            best.lock()
+                .as_ref()
+                .map_or(window_alpha, |(score, _)| window_alpha.max(*score)),
+        );
+        let mut variation = vec![play];
+        let mut child_variation = Vec::new();
+        let score = -search.negamax_child(
+            &mut game,
+            bot_player,
+            1,
+            alpha,
+            beta,
+            first,
+            &mut child_variation,
+        )?;
+        if score >= beta {
+            cut.store(true, Relaxed);
+        }
+        let mut best = best.lock();
+        let is_best = best.as_ref().is_none_or(|(best_score, best_variation)| {
+            score > *best_score
+                || (score == *best_score
+                    && config.reproducible()
+                    && rank(play) < rank(best_variation[0]))
+        });
+        if is_best {
+            variation.append(&mut child_variation);
+            *best = Some((score, variation));
+        }
+        Some(())
+    };
+
+    // the entry of the previous search, usually with a lower depth
+    let entry = table.get(game.hash_key());
+    let explore_root = |alpha, beta| {
+        search.stats.node(0);
+        *best.lock() = None;
+        cut.store(false, Relaxed);
+        let mut possible_plays = possible_plays.clone();
+        // the best play of the previous search is likely the best
+        let hash_play = entry.and_then(|entry| entry.best_play);
+        search
+            .ordering
+            .sort(&mut possible_plays, |play| *play, 0, hash_play);
+        // the first play is explored alone, so that the others can be cut by its score
+        let (first_play, other_plays) = possible_plays.split_first()?;
+        explore_play(*first_play, true, alpha, beta)?;
+        if config.multi_threading {
+            other_plays
+                .par_iter()
+                .try_for_each(|play| explore_play(*play, false, alpha, beta))?;
+        } else {
+            other_plays
+                .iter()
+                .try_for_each(|play| explore_play(*play, false, alpha, beta))?;
+        }
+        best.lock().as_ref().map(|(score, _)| *score)
+    };
+    // the aspiration window is around the weight found by the previous search
+    let guess = entry
+        .filter(|entry| entry.bound == Bound::Exact)
+        .map(|entry| entry.score);
+    let completed = config
+        .aspiration
+        .explore(guess, &search.stats, explore_root)
+        .is_some();
+    let stats = search.stats.finish(max_depth, completed);
+    log::debug!("{stats}");
+    let variation = best
+        .into_inner()
+        .filter(|_| completed)
+        .map(|(score, variation)| {
+            table.store(
+                game.hash_key(),
+                max_depth,
+                score,
+                Bound::Exact,
+                Some(variation[0]),
+            );
+            // the weight of a node is the weight of its best child, all the way down
+            variation.into_iter().map(|play| (play, score)).collect()
+        });
+    (variation, stats)
+}
+
+/// Like `best_play`, but every play is searched with a full window so that they all get an exact
+/// weight, see `GameNode::analyze`.
+///
+/// Returns the plays sorted from the best to the worst for `bot_player`, who must be the next one
+/// to play, or None if the search was aborted, and the stats of the search.
+#[allow(clippy::too_many_arguments)]
+pub fn analyze<G: Game, E: Evaluator<G>>(
+    game: &G,
+    bot_player: G::Player,
+    max_depth: u32,
+    config: &SearchConfig<G::Score>,
+    evaluator: &E,
+    table: &TranspositionTable<G>,
+    deadline: Option<Instant>,
+    cancellation: &CancellationToken,
+) -> (Option<Vec<AnalyzedPlay<G>>>, SearchStats) {
+    table.new_search();
+    let search = Search::new(
+        bot_player,
+        max_depth,
+        config,
+        0,
+        evaluator,
+        table,
+        deadline,
+        cancellation,
+    );
+    search.stats.node(0);
+
+    let analyze_play = |play: &G::InputCoordinate| {
+        let mut game = game.clone();
+        game.play(bot_player, *play).unwrap(); // should not panic as play is a possible play
+        let mut child_variation = Vec::new();
+        let weight = -search.negamax(
+            &mut game,
+            bot_player.other(),
+            1,
+            G::Score::MIN(),
+            G::Score::MAX(),
+            &mut child_variation,
+        )?;
+        Some(AnalyzedPlay {
+            play: *play,
+            weight,
+            variation: std::iter::once(*play)
+                .chain(child_variation)
+                .map(|play| (play, weight))
+                .collect(),
+        })
+    };
+    let analysis: Option<Vec<_>> = if game.get_winner().is_some() || game.is_full() {
+        // nothing left to play
+        Some(Vec::new())
+    } else if config.multi_threading {
+        let plays = config.play_order.possible_plays(game);
+        plays.par_iter().map(analyze_play).collect()
+    } else {
+        let plays = config.play_order.possible_plays(game);
+        plays.iter().map(analyze_play).collect()
+    };
+
+    let analysis = analysis.map(|mut analysis| {
+        sort_analysis(&mut analysis, true);
+        analysis
+    });
+    let stats = search.stats.finish(max_depth, analysis.is_some());
+    log::debug!("{stats}");
+    (analysis, stats)
+}
+
+impl<G: Game, E: Evaluator<G>> Search<'_, G, E> {
+    /// Negamax search on a game that is played and undone in place, `ply` plays after the root,
+    /// like `GameNode::negamax`: the weights are from the point of view of `now_playing`.
+    ///
+    /// The real weight is returned if it is strictly between `alpha` and `beta`, otherwise the
+    /// returned weight is only a bound of the real one. Returns None if the search was aborted.
+    ///
+    /// `variation` is filled with the best plays expected from `game`, which are only meaningful
+    /// when the real weight is returned. It stops early at the positions found in the table.
+    fn negamax(
+        &self,
+        game: &mut G,
+        now_playing: G::Player,
+        ply: u32,
+        mut alpha: G::Score,
+        mut beta: G::Score,
+        variation: &mut Vec<G::InputCoordinate>,
+    ) -> Option<G::Score> {
+        self.stats.node(ply);
+        variation.clear();
+
+        // converts between the weights of the bot and the ones of now_playing, both ways
+        let maximize = now_playing == self.bot_player;
+        let for_now_playing = |weight: G::Score| if maximize { weight } else { -weight };
+        // the bounds of the bot that are lower and upper bounds for now_playing
+        let (lower_bound, upper_bound) = if maximize {
+            (Bound::Lower, Bound::Upper)
+        } else {
+            (Bound::Upper, Bound::Lower)
+        };
+
+        // same order of checks as GameNode::negamax
+        if ply >= self.max_depth {
+            return Some(for_now_playing(self.leaf_weight(game, ply)));
+        }
+        if let Some(winner) = game.get_winner() {
+            return Some(for_now_playing(self.win_weight(winner, ply)));
+        }
+        if game.is_full() {
+            return Some(for_now_playing(self.draw_weight()));
+        }
+        if self.should_stop() {
+            return None;
+        }
+
+        let key = game.hash_key();
+        let remaining_depth = self.max_depth - ply;
+        let entry = self.table.get(key);
+        if let Some(entry) = entry {
+            if self.is_usable(&entry, remaining_depth) {
+                let score = for_now_playing(self.table_weight(entry.score, ply));
+                if entry.bound == Bound::Exact {
+                    self.stats.table_hit();
+                    return Some(score);
+                } else if entry.bound == lower_bound {
+                    alpha = alpha.max(score);
+                } else {
+                    beta = beta.min(score);
+                }
+                if alpha >= beta {
+                    self.stats.table_hit();
+                    return Some(score);
+                }
+            }
+        }
+        let (original_alpha, original_beta) = (alpha, beta);
+
+        let mut plays = self.config.play_order.possible_plays(game);
+        let hash_play = entry.and_then(|entry| entry.best_play);
+        self.ordering.sort(&mut plays, |play| *play, ply, hash_play);
+        let best = self.negamax_children(game, now_playing, ply, &plays, alpha, beta, variation)?;
+
+        let bound = if best <= original_alpha {
+            upper_bound
+        } else if best >= original_beta {
+            lower_bound
+        } else {
+            Bound::Exact
+        };
+        // when all the plays were cut, none of them is known to be the best
+        let best_play = variation.first().copied().filter(|_| bound != upper_bound);
+        self.table.store(
+            key,
+            remaining_depth,
+            self.table_score(for_now_playing(best), ply),
+            bound,
+            best_play,
+        );
+        Some(best)
+    }
+
+    /// Explores `plays` from `game` for `negamax`, like `GameNode::negamax_children`, and returns
+    /// the weight of the best one for `now_playing`, or None if the search was aborted.
+    /// `variation` is filled with the best play followed by the plays expected after it.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax_children(
+        &self,
+        game: &mut G,
+        now_playing: G::Player,
+        ply: u32,
+        plays: &[G::InputCoordinate],
+        mut alpha: G::Score,
+        beta: G::Score,
+        variation: &mut Vec<G::InputCoordinate>,
+    ) -> Option<G::Score> {
+        let remaining_depth = self.max_depth - ply;
+        // the younger plays of a split position wait for the first one, see SearchConfig::splits
+        let sequential = if self.config.splits(remaining_depth) {
+            1.min(plays.len())
+        } else {
+            plays.len()
+        };
+        let (sequential_plays, parallel_plays) = plays.split_at(sequential);
+        let mut best = G::Score::MIN();
+        let mut child_variation = Vec::new();
+        for (index, &play) in sequential_plays.iter().enumerate() {
+            game.play(now_playing, play).unwrap(); // should not panic as play is a possible play
+            let score = self.negamax_child(
+                game,
+                now_playing,
+                ply + 1,
+                alpha,
+                beta,
+                index == 0,
+                &mut child_variation,
+            );
+            game.undo();
+            let score = -score?;
+
+            if score > best || variation.is_empty() {
+                variation.clear();
+                variation.push(play);
+                variation.append(&mut child_variation);
+            }
+            best = best.max(score);
+            alpha = alpha.max(best);
+            if alpha >= beta {
+                self.stats.cutoff();
+                self.ordering.cutoff(play, ply, remaining_depth);
+                return Some(best);
+            }
+        }
+        if parallel_plays.is_empty() {
+            return Some(best);
+        }
+
+        let cut = AtomicBool::new(false);
+        // each thread plays on its own copy of the game
+        let parallel_best = parallel_plays
+            .par_iter()
+            .map(|&play| {
+                if cut.load(Relaxed) {
+                    // the position is already too good to be chosen by the opponent
+                    return Some((G::Score::MIN(), None));
+                }
+                let mut game = game.clone();
+                game.play(now_playing, play).unwrap(); // should not panic as play is a possible play
+                let mut child_variation = Vec::new();
+                let score = -self.negamax_child(
+                    &mut game,
+                    now_playing,
+                    ply + 1,
+                    alpha,
+                    beta,
+                    false,
+                    &mut child_variation,
+                )?;
+                if score >= beta {
+                    cut.store(true, Relaxed);
+                }
+                Some((score, Some((play, child_variation))))
+            })
+            .try_reduce(|| (G::Score::MIN(), None), |a, b| Some(best_child(a, b)))?;
+        if let (score, Some((play, mut child_variation))) = parallel_best {
+            if score > best {
+                best = score;
+                variation.clear();
+                variation.push(play);
+                variation.append(&mut child_variation);
+            }
+            if best >= beta {
+                self.stats.cutoff();
+                self.ordering.cutoff(play, ply, remaining_depth);
+            }
+        }
+        Some(best)
+    }
+
+    /// Explores a child with `negamax`, with the window of its parent, like
+    /// `GameNode::negamax_child`. With PVS, the children after the first one are explored with a
+    /// null window first, see `Algorithm`.
+    ///
+    /// Returns the weight of the child for the player playing it, like `negamax`.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax_child(
+        &self,
+        game: &mut G,
+        parent_playing: G::Player,
+        ply: u32,
+        alpha: G::Score,
+        beta: G::Score,
+        first: bool,
+        variation: &mut Vec<G::InputCoordinate>,
+    ) -> Option<G::Score> {
+        let now_playing = parent_playing.other();
+        // the window can be empty at the root, once a play is known to win
+        if first || self.config.algorithm == Algorithm::AlphaBeta || alpha >= beta {
+            return self.negamax(game, now_playing, ply, -beta, -alpha, variation);
+        }
+        let score = -self.negamax(
+            game,
+            now_playing,
+            ply,
+            -alpha - G::Score::from(1),
+            -alpha,
+            variation,
+        )?;
+        if score > alpha && score < beta {
+            // better than the best child so far, its real weight is needed
+            self.stats.research();
+            return self.negamax(game, now_playing, ply, -beta, -alpha, variation);
+        }
+        Some(-score)
+    }
+}
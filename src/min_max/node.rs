@@ -33,7 +33,7 @@ impl<G: Game> GameNode<G> {
      */
     pub fn try_into_child(mut self, play: G::InputCoordinate) -> (bool, Self) {
         let mut new_children = Vec::with_capacity(self.children.len());
-        for (coord, mut child) in self.children.into_iter() {
+        for (coord, child) in self.children.into_iter() {
             if coord == play {
                 return (true, child);
             }
@@ -83,7 +83,7 @@ impl<G: Game> GameNode<G> {
         if max_depth == 0 {
             let depth = self.count_depth();
             if depth > 0 {
-                s.push_str(&*format!("{} non shown", depth));
+                s.push_str(&format!("{} non shown", depth));
             }
             return s;
         }
@@ -0,0 +1,74 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use parking_lot::{Mutex, RwLock};
+
+use crate::game::Game;
+
+/// Plays that cut the search in other positions are likely to cut it again, whatever the game:
+/// this remembers them during a search to explore them first.
+///
+/// - the play found best for the position by a previous search (the hash play, from the
+///   transposition table) comes first
+/// - then the killer plays: the last two plays that caused a cutoff at the same distance from the
+///   root, in any position
+/// - then the other plays, by their history: how much they caused cutoffs anywhere, the deeper
+///   the exploration below the cutoff the more
+pub(crate) struct MoveOrdering<G: Game> {
+    /// The killer plays of each ply, the most recent first
+    killers: Vec<Mutex<[Option<G::InputCoordinate>; 2]>>,
+    history: RwLock<HashMap<G::InputCoordinate, u64>>,
+}
+
+impl<G: Game> MoveOrdering<G> {
+    /// Creates the ordering of a search exploring `max_depth` plays ahead
+    pub(crate) fn new(max_depth: u32) -> Self {
+        Self {
+            killers: (0..=max_depth).map(|_| Mutex::new([None; 2])).collect(),
+            history: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Records that `play` caused a cutoff `ply` plays after the root, with `remaining_depth`
+    /// plays left to explore
+    pub(crate) fn cutoff(&self, play: G::InputCoordinate, ply: u32, remaining_depth: u32) {
+        if let Some(killers) = self.killers.get(ply as usize) {
+            let mut killers = killers.lock();
+            if killers[0] != Some(play) {
+                killers[1] = killers[0];
+                killers[0] = Some(play);
+            }
+        }
+        let bonus = remaining_depth as u64 * remaining_depth as u64;
+        *self.history.write().entry(play).or_insert(0) += bonus;
+    }
+
+    /// Sorts `items` so that the most promising plays are explored first, see `MoveOrdering`.
+    /// Plays that are equally promising stay in the same order.
+    pub(crate) fn sort<T>(
+        &self,
+        items: &mut [T],
+        play: impl Fn(&T) -> G::InputCoordinate,
+        ply: u32,
+        hash_play: Option<G::InputCoordinate>,
+    ) {
+        let killers = self
+            .killers
+            .get(ply as usize)
+            .map_or([None; 2], |killers| *killers.lock());
+        let history = self.history.read();
+        items.sort_by_cached_key(|item| {
+            let play = play(item);
+            let rank = if Some(play) == hash_play {
+                0
+            } else if Some(play) == killers[0] {
+                1
+            } else if Some(play) == killers[1] {
+                2
+            } else {
+                3
+            };
+            (rank, Reverse(history.get(&play).copied().unwrap_or(0)))
+        });
+    }
+}
@@ -0,0 +1,86 @@
+use crate::scalar::Scalar;
+
+/// The wins and losses are scored by their distance from the root of the search: a win `n` plays
+/// after it weighs `MAX - n`, and a loss `MIN + n`, so that the bot wins as fast as possible and
+/// loses as late as possible. The other weights stay at least this far from `MAX` and `MIN`.
+pub const MATE_RANGE: i32 = 1000;
+
+/// What the weight of a position means for the bot, see `Outcome::decode`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome<S> {
+    /// The bot wins in this many plays (of both players), whatever its opponent plays
+    Win(u32),
+    /// The bot loses in this many plays (of both players) if its opponent plays well
+    Loss(u32),
+    /// Nothing is decided as far as the search explored: the weight comes from the evaluator, or
+    /// from a draw
+    Heuristic(S),
+}
+
+/// The exact result of a game for a player if both players play perfectly, see
+/// `Bot::solution`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Solution {
+    /// The player wins with their play that is this many plays (of both players) away
+    Win(u32),
+    /// The other player wins with their play that is this many plays away
+    Loss(u32),
+    Draw,
+}
+
+impl<S: Scalar> Outcome<S> {
+    /// The meaning of `weight`, a weight from the point of view of the bot and relative to the
+    /// root of the search, like the weights of `GameNode` or of `Variation`
+    pub fn decode(weight: S) -> Self {
+        if weight > S::MAX().add_towards_0(MATE_RANGE) {
+            Outcome::Win(distance(S::MAX() - weight))
+        } else if weight < S::MIN().add_towards_0(MATE_RANGE) {
+            Outcome::Loss(distance(weight - S::MIN()))
+        } else {
+            Outcome::Heuristic(weight)
+        }
+    }
+
+    /// The weight meaning this outcome, see `decode`. The distances are capped to `MATE_RANGE`,
+    /// and the heuristic weights are brought back between the weights of the wins and losses.
+    pub fn encode(self) -> S {
+        let cap = |plies: u32| S::from(plies.min(MATE_RANGE as u32 - 1) as i32);
+        match self {
+            Outcome::Win(plies) => S::MAX() - cap(plies),
+            Outcome::Loss(plies) => S::MIN() + cap(plies),
+            Outcome::Heuristic(weight) => weight.clamp(
+                S::MIN().add_towards_0(MATE_RANGE),
+                S::MAX().add_towards_0(MATE_RANGE),
+            ),
+        }
+    }
+
+    /// The exact result meant by the weight of an exploration that reached the end of the game
+    /// everywhere, where the heuristic weights can only be draws
+    pub fn solution(self) -> Solution {
+        match self {
+            Outcome::Win(plies) => Solution::Win(plies),
+            Outcome::Loss(plies) => Solution::Loss(plies),
+            Outcome::Heuristic(_) => Solution::Draw,
+        }
+    }
+
+    /// The same outcome seen from `plies` plays later (or earlier if negative)
+    pub(crate) fn shift(self, plies: i32) -> Self {
+        let shift = |distance: u32| distance.saturating_add_signed(-plies);
+        match self {
+            Outcome::Win(distance) => Outcome::Win(shift(distance)),
+            Outcome::Loss(distance) => Outcome::Loss(shift(distance)),
+            heuristic => heuristic,
+        }
+    }
+}
+
+/// A distance from `MAX` or `MIN`, which is less than `MATE_RANGE`
+fn distance<S: Scalar>(difference: S) -> u32 {
+    difference
+        .try_into()
+        .ok()
+        .and_then(|difference: i32| u32::try_from(difference).ok())
+        .expect("the distance should be less than MATE_RANGE")
+}
@@ -0,0 +1,180 @@
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicU32, AtomicU64};
+use std::time::{Duration, Instant};
+
+/// What happened during a search, see `GameNode::explore_children`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Number of plays the search had to explore ahead
+    pub depth: u32,
+    /// False if the search was aborted before the end
+    pub completed: bool,
+    /// Number of positions visited, including the root and the leaves
+    pub nodes: u64,
+    /// Number of positions where the exploration stopped because of the depth, and that were
+    /// scored by the evaluator
+    pub leaf_evaluations: u64,
+    /// Number of positions whose remaining children were not explored because of alpha-beta
+    /// pruning
+    pub cutoffs: u64,
+    /// Number of positions whose weight was found in the transposition table
+    pub table_hits: u64,
+    /// Number of positions explored again with a full window, after a null window showed that
+    /// they were better than expected (see `Algorithm::PrincipalVariation`)
+    pub researches: u64,
+    /// Number of times the root was explored again with a wider window, after its weight was
+    /// found outside of the aspiration window (see `Aspiration`)
+    pub aspiration_researches: u32,
+    /// Deepest position visited, in plays after the root. Lower than `depth` if all the games
+    /// ended before
+    pub max_depth_reached: u32,
+    pub elapsed: Duration,
+    /// Positions visited by each thread: the threads of the rayon pool in order, then the
+    /// thread(s) outside the pool, like the one that started the search
+    pub nodes_per_thread: Vec<u64>,
+}
+
+impl SearchStats {
+    pub fn nodes_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            0.0
+        } else {
+            self.nodes as f64 / seconds
+        }
+    }
+}
+
+/// Writes big numbers like 1.23M
+fn short_number(number: f64) -> String {
+    if number >= 1e9 {
+        format!("{:.2}G", number / 1e9)
+    } else if number >= 1e6 {
+        format!("{:.2}M", number / 1e6)
+    } else if number >= 1e3 {
+        format!("{:.2}K", number / 1e3)
+    } else {
+        format!("{}", number)
+    }
+}
+
+impl Display for SearchStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "depth {}{}: {} nodes in {}ms ({} nodes/s), {} evaluations, {} cutoffs, {} table hits, {} re-searches, {} aspiration re-searches, {} plays deep",
+            self.depth,
+            if self.completed { "" } else { " (aborted)" },
+            short_number(self.nodes as f64),
+            self.elapsed.as_millis(),
+            short_number(self.nodes_per_second().round()),
+            short_number(self.leaf_evaluations as f64),
+            short_number(self.cutoffs as f64),
+            short_number(self.table_hits as f64),
+            short_number(self.researches as f64),
+            self.aspiration_researches,
+            self.max_depth_reached,
+        )
+    }
+}
+
+/// Counters of one thread. Each thread has its own, on its own cache line, so that counting does
+/// not slow down the other threads.
+#[derive(Default)]
+#[repr(align(128))]
+struct ThreadCounters {
+    nodes: AtomicU64,
+    leaf_evaluations: AtomicU64,
+    cutoffs: AtomicU64,
+    table_hits: AtomicU64,
+    researches: AtomicU64,
+    max_depth_reached: AtomicU32,
+}
+
+/// Collects the `SearchStats` of a search from all the threads
+pub(crate) struct StatsRecorder {
+    /// One per thread of the rayon pool, and a last one for the other threads
+    threads: Vec<ThreadCounters>,
+    /// Only counted by the thread that started the search
+    aspiration_researches: AtomicU32,
+    start: Instant,
+}
+
+impl StatsRecorder {
+    pub(crate) fn new() -> Self {
+        Self {
+            threads: (0..=rayon::current_num_threads())
+                .map(|_| ThreadCounters::default())
+                .collect(),
+            aspiration_researches: AtomicU32::new(0),
+            start: Instant::now(),
+        }
+    }
+
+    fn counters(&self) -> &ThreadCounters {
+        let outside_pool = self.threads.len() - 1;
+        let index = rayon::current_thread_index()
+            .filter(|&index| index < outside_pool)
+            .unwrap_or(outside_pool);
+        &self.threads[index]
+    }
+
+    /// Records a visit of a position `depth` plays after the root
+    pub(crate) fn node(&self, depth: u32) {
+        let counters = self.counters();
+        counters.nodes.fetch_add(1, Relaxed);
+        counters.max_depth_reached.fetch_max(depth, Relaxed);
+    }
+
+    pub(crate) fn leaf_evaluation(&self) {
+        self.counters().leaf_evaluations.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn cutoff(&self) {
+        self.counters().cutoffs.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn table_hit(&self) {
+        self.counters().table_hits.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn research(&self) {
+        self.counters().researches.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn aspiration_research(&self) {
+        self.aspiration_researches.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn finish(&self, depth: u32, completed: bool) -> SearchStats {
+        let sum = |counter: fn(&ThreadCounters) -> &AtomicU64| {
+            self.threads
+                .iter()
+                .map(|counters| counter(counters).load(Relaxed))
+                .sum()
+        };
+        SearchStats {
+            depth,
+            completed,
+            nodes: sum(|counters| &counters.nodes),
+            leaf_evaluations: sum(|counters| &counters.leaf_evaluations),
+            cutoffs: sum(|counters| &counters.cutoffs),
+            table_hits: sum(|counters| &counters.table_hits),
+            researches: sum(|counters| &counters.researches),
+            aspiration_researches: self.aspiration_researches.load(Relaxed),
+            max_depth_reached: self
+                .threads
+                .iter()
+                .map(|counters| counters.max_depth_reached.load(Relaxed))
+                .max()
+                .unwrap_or(0),
+            elapsed: self.start.elapsed(),
+            nodes_per_thread: self
+                .threads
+                .iter()
+                .map(|counters| counters.nodes.load(Relaxed))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,663 @@
+#[cfg(test)]
+mod min_max_tests {
+    use std::num::{NonZeroU8, NonZeroUsize};
+
+    use crate::game::connect4::ConnectFour;
+    use crate::game::evaluator::GameScore;
+    use crate::game::player::Player;
+    use crate::game::Game;
+    use crate::min_max::cancellation::CancellationToken;
+    use crate::min_max::config::{Aspiration, DepthPenalty, SearchConfig};
+    use crate::min_max::make_unmake;
+    use crate::min_max::node::GameNode;
+    use crate::min_max::ordering::MoveOrdering;
+    use crate::min_max::outcome::{Outcome, MATE_RANGE};
+    use crate::min_max::transposition::{Bound, TranspositionTable};
+    use crate::min_max::{Algorithm, Search};
+
+    #[test]
+    fn table_replacement() {
+        // a single slot, so that every key collides
+        let table: TranspositionTable<ConnectFour> = TranspositionTable::new(1);
+
+        table.store(1, 5, 10, Bound::Exact, None);
+        assert_eq!(table.get(1).unwrap().score, 10);
+        assert!(table.get(2).is_none());
+
+        // shallower entry from the same search does not replace a deeper one
+        table.store(2, 3, 20, Bound::Exact, None);
+        assert!(table.get(2).is_none());
+        assert_eq!(table.get(1).unwrap().depth, 5);
+
+        // same key is always updated
+        table.store(1, 2, 30, Bound::Lower, None);
+        let entry = table.get(1).unwrap();
+        assert_eq!(
+            (entry.depth, entry.score, entry.bound),
+            (2, 30, Bound::Lower)
+        );
+
+        // entries from an older search are replaced
+        table.store(1, 10, 40, Bound::Exact, None);
+        table.new_search();
+        table.store(2, 1, 50, Bound::Upper, None);
+        assert!(table.get(1).is_none());
+        assert_eq!(table.get(2).unwrap().score, 50);
+    }
+
+    #[test]
+    fn table_keeps_best_play() {
+        let table: TranspositionTable<ConnectFour> = TranspositionTable::new(1024);
+        let play = NonZeroUsize::new(3).unwrap();
+        table.store(1, 2, 10, Bound::Exact, Some(play));
+        assert_eq!(table.get(1).unwrap().best_play, Some(play));
+
+        // a deeper search where all the plays were cut still remembers the best one so far
+        table.store(1, 4, 5, Bound::Upper, None);
+        let entry = table.get(1).unwrap();
+        assert_eq!((entry.depth, entry.best_play), (4, Some(play)));
+    }
+
+    #[test]
+    fn move_ordering() {
+        let ordering: MoveOrdering<ConnectFour> = MoveOrdering::new(4);
+        let column = |column| NonZeroUsize::new(column).unwrap();
+        let mut plays: Vec<_> = (1..=7).map(column).collect();
+
+        // nothing known yet, the order is kept
+        ordering.sort(&mut plays, |play| *play, 2, None);
+        assert_eq!(plays, (1..=7).map(column).collect::<Vec<_>>());
+
+        ordering.cutoff(column(5), 1, 3);
+        ordering.cutoff(column(5), 1, 3);
+        ordering.cutoff(column(6), 2, 2);
+        ordering.cutoff(column(7), 2, 2);
+        ordering.sort(&mut plays, |play| *play, 2, Some(column(1)));
+        // hash play, killers of ply 2 (most recent first), then by history
+        let expected: Vec<_> = [1, 7, 6, 5, 2, 3, 4].into_iter().map(column).collect();
+        assert_eq!(plays, expected);
+    }
+
+    #[test]
+    fn configured_weights() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        // only column 4 is left, with 3 cells, and filling it is a draw
+        let mut game = ConnectFour::new();
+        let mut player = p1;
+        for column in "547125662261271266215743771576315353334".chars() {
+            let column = column.to_digit(10).unwrap() as usize;
+            game.play(player, NonZeroUsize::new(column).unwrap())
+                .unwrap();
+            player = player.other();
+        }
+        let configs = [
+            SearchConfig::default(),
+            SearchConfig::default().with_contempt(0),
+            SearchConfig::default().with_contempt(-100),
+        ];
+        // the game is full when the exploration stops at depth 3, and before at depth 5
+        for (config, max_depth) in configs
+            .into_iter()
+            .flat_map(|config| [(config, 3), (config, 5)])
+        {
+            let mut game_tree = GameNode::new_root(game.clone(), player, 39);
+            game_tree.explore_children(
+                player,
+                max_depth,
+                &config,
+                &GameScore,
+                &TranspositionTable::new(1024),
+                None,
+                &CancellationToken::new(),
+            );
+            assert_eq!(game_tree.weight(), Some(config.draw_weight()));
+            let (variation, _) = make_unmake::best_play(
+                &game,
+                player,
+                max_depth,
+                &config,
+                &GameScore,
+                &TranspositionTable::new(1024),
+                None,
+                &CancellationToken::new(),
+            );
+            assert_eq!(variation.unwrap()[0].1, config.draw_weight());
+        }
+
+        // nobody can win in 4 plays from the start
+        let config = SearchConfig::default().with_game_score(false);
+        let mut game_tree = GameNode::new_root(ConnectFour::new(), p1, 0);
+        game_tree.explore_children(
+            p1,
+            4,
+            &config,
+            &GameScore,
+            &TranspositionTable::new(1024),
+            None,
+            &CancellationToken::new(),
+        );
+        assert_eq!(game_tree.weight(), Some(0));
+    }
+
+    #[test]
+    fn table_does_not_change_result() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+        let mut game = ConnectFour::new();
+        for (player, column) in [(p1, 4), (p2, 4), (p1, 3), (p2, 5)] {
+            game.play(player, NonZeroUsize::new(column).unwrap())
+                .unwrap();
+        }
+
+        let mut with_table = GameNode::new_root(game.clone(), p1, 4);
+        with_table.explore_children(
+            p1,
+            5,
+            &SearchConfig::default(),
+            &GameScore,
+            &TranspositionTable::default(),
+            None,
+            &CancellationToken::new(),
+        );
+        let mut tiny_table = GameNode::new_root(game, p1, 4);
+        tiny_table.explore_children(
+            p1,
+            5,
+            &SearchConfig::default(),
+            &GameScore,
+            &TranspositionTable::new(1),
+            None,
+            &CancellationToken::new(),
+        );
+
+        assert_eq!(with_table.weight(), tiny_table.weight());
+    }
+
+    #[test]
+    fn aspiration_windows_do_not_change_weight() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+        let mut game = ConnectFour::new();
+        for (player, column) in [(p1, 4), (p2, 4), (p1, 3), (p2, 5)] {
+            game.play(player, NonZeroUsize::new(column).unwrap())
+                .unwrap();
+        }
+        let full_window = SearchConfig::default().with_aspiration(Aspiration::None);
+        let mut game_tree = GameNode::new_root(game.clone(), p1, 4);
+        game_tree.explore_children(
+            p1,
+            5,
+            &full_window,
+            &GameScore,
+            &TranspositionTable::new(1 << 20),
+            None,
+            &CancellationToken::new(),
+        );
+        let weight = game_tree.weight().unwrap();
+
+        let narrow_window = SearchConfig::default().with_aspiration(Aspiration::Window {
+            initial_width: 1,
+            growth: 2,
+        });
+        // the guesses fail low, fail high and succeed
+        for guess in [weight + 1000, weight - 1000, weight] {
+            let mut game_tree = GameNode::new_root(game.clone(), p1, 4);
+            game_tree.set_weight(Some(guess));
+            let stats = game_tree.explore_children(
+                p1,
+                5,
+                &narrow_window,
+                &GameScore,
+                &TranspositionTable::new(1 << 20),
+                None,
+                &CancellationToken::new(),
+            );
+            assert_eq!(game_tree.weight(), Some(weight));
+            assert_eq!(stats.aspiration_researches > 0, guess != weight);
+
+            let table = TranspositionTable::new(1 << 20);
+            table.store(game.hash_key(), 4, guess, Bound::Exact, None);
+            let (variation, stats) = make_unmake::best_play(
+                &game,
+                p1,
+                5,
+                &narrow_window,
+                &GameScore,
+                &table,
+                None,
+                &CancellationToken::new(),
+            );
+            assert_eq!(variation.unwrap()[0].1, weight);
+            assert_eq!(stats.aspiration_researches > 0, guess != weight);
+        }
+    }
+
+    #[test]
+    fn cancelled_search_keeps_tree_usable() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let table = TranspositionTable::default();
+        let mut game_tree = GameNode::new_root(ConnectFour::new(), p1, 0);
+
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        assert!(
+            !game_tree
+                .explore_children(
+                    p1,
+                    6,
+                    &SearchConfig::default(),
+                    &GameScore,
+                    &table,
+                    None,
+                    &cancellation
+                )
+                .completed
+        );
+        assert!(game_tree.weight().is_none());
+        assert!(game_tree.best_play().is_none());
+
+        cancellation.reset();
+        assert!(
+            game_tree
+                .explore_children(
+                    p1,
+                    6,
+                    &SearchConfig::default(),
+                    &GameScore,
+                    &table,
+                    None,
+                    &cancellation
+                )
+                .completed
+        );
+        assert!(game_tree.best_play().is_some());
+    }
+
+    #[test]
+    fn make_unmake_same_weight_as_tree() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let positions: [&[usize]; 4] = [
+            &[],
+            &[4, 4, 3, 5],
+            &[4, 3, 4, 3, 4],
+            &[1, 2, 3, 4, 5, 6, 7, 7, 6, 5, 4, 3, 2, 1],
+        ];
+        for columns in positions {
+            let mut game = ConnectFour::new();
+            let mut player = p1;
+            for column in columns {
+                game.play(player, NonZeroUsize::new(*column).unwrap())
+                    .unwrap();
+                player = player.other();
+            }
+            let depth = columns.len() as u32;
+            for max_depth in 1..=5 {
+                let mut game_tree = GameNode::new_root(game.clone(), player, depth);
+                game_tree.explore_children(
+                    player,
+                    max_depth,
+                    &SearchConfig::default(),
+                    &GameScore,
+                    &TranspositionTable::default(),
+                    None,
+                    &CancellationToken::new(),
+                );
+                let (_, weight) = make_unmake::best_play(
+                    &game,
+                    player,
+                    max_depth,
+                    &SearchConfig::default(),
+                    &GameScore,
+                    &TranspositionTable::default(),
+                    None,
+                    &CancellationToken::new(),
+                )
+                .0
+                .unwrap()[0];
+                assert_eq!(
+                    game_tree.weight(),
+                    Some(weight),
+                    "plays {columns:?}, depth {max_depth}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn search_stats() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let table = TranspositionTable::default();
+        let cancellation = CancellationToken::new();
+
+        let mut game_tree = GameNode::new_root(ConnectFour::new(), p1, 0);
+        let stats = game_tree.explore_children(
+            p1,
+            5,
+            &SearchConfig::default(),
+            &GameScore,
+            &table,
+            None,
+            &cancellation,
+        );
+        assert!(stats.completed);
+        assert_eq!(stats.depth, 5);
+        assert_eq!(stats.max_depth_reached, 5);
+        assert!(stats.nodes > stats.leaf_evaluations);
+        assert!(stats.leaf_evaluations > 0);
+        assert!(stats.cutoffs > 0);
+        assert_eq!(stats.nodes_per_thread.iter().sum::<u64>(), stats.nodes);
+
+        // the same positions are in the table
+        let mut game_tree = GameNode::new_root(ConnectFour::new(), p1, 0);
+        let stats = game_tree.explore_children(
+            p1,
+            5,
+            &SearchConfig::default(),
+            &GameScore,
+            &table,
+            None,
+            &cancellation,
+        );
+        assert!(stats.table_hits > 0);
+
+        let (play, stats) = make_unmake::best_play(
+            &ConnectFour::new(),
+            p1,
+            5,
+            &SearchConfig::default(),
+            &GameScore,
+            &TranspositionTable::default(),
+            None,
+            &cancellation,
+        );
+        assert!(play.is_some());
+        assert!(stats.completed);
+        assert_eq!(stats.max_depth_reached, 5);
+        assert!(stats.leaf_evaluations > 0);
+        assert!(stats.cutoffs > 0);
+        assert_eq!(stats.nodes_per_thread.iter().sum::<u64>(), stats.nodes);
+
+        cancellation.cancel();
+        let (play, stats) = make_unmake::best_play(
+            &ConnectFour::new(),
+            p1,
+            5,
+            &SearchConfig::default(),
+            &GameScore,
+            &TranspositionTable::default(),
+            None,
+            &cancellation,
+        );
+        assert!(play.is_none());
+        assert!(!stats.completed);
+    }
+
+    #[test]
+    fn principal_variation_of_forced_win() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let p2 = NonZeroU8::new(2).unwrap();
+        let mut game = ConnectFour::new();
+        for (player, column) in [(p1, 3), (p2, 3), (p1, 4), (p2, 4)] {
+            game.play(player, NonZeroUsize::new(column).unwrap())
+                .unwrap();
+        }
+        // p1 plays 2 or 5 and wins on one side or the other of the three pieces, whatever p2 does
+        let mut game_tree = GameNode::new_root(game.clone(), p1, 4);
+        game_tree.explore_children(
+            p1,
+            5,
+            &SearchConfig::default(),
+            &GameScore,
+            &TranspositionTable::default(),
+            None,
+            &CancellationToken::new(),
+        );
+        let (variation, _) = make_unmake::best_play(
+            &game,
+            p1,
+            5,
+            &SearchConfig::default(),
+            &GameScore,
+            &TranspositionTable::default(),
+            None,
+            &CancellationToken::new(),
+        );
+        // the win is 3 plays away
+        assert_eq!(game_tree.weight(), Some(i32::MAX - 3));
+        for variation in [game_tree.principal_variation(5), variation.unwrap()] {
+            assert_eq!(variation.len(), 3, "{variation:?}");
+            assert!([2, 5].contains(&variation[0].0.get()));
+            let mut game = game.clone();
+            let mut player = p1;
+            for (play, weight) in variation {
+                assert_eq!(Some(weight), game_tree.weight());
+                game.play(player, play).unwrap();
+                player = player.other();
+            }
+            assert_eq!(game.get_winner(), Some(p1));
+        }
+
+        // the line is cut at the depth of the exploration
+        assert_eq!(game_tree.principal_variation(2).len(), 2);
+        assert!(GameNode::new_root(game, p1, 4)
+            .principal_variation(5)
+            .is_empty());
+    }
+
+    #[test]
+    fn outcome_weights() {
+        for outcome in [
+            Outcome::Win(1),
+            Outcome::Win(42),
+            Outcome::Loss(0),
+            Outcome::Loss(7),
+            Outcome::Heuristic(0),
+            Outcome::Heuristic(-1234),
+        ] {
+            assert_eq!(Outcome::decode(outcome.encode()), outcome);
+        }
+        assert_eq!(Outcome::<i32>::Win(3).encode(), i32::MAX - 3);
+        assert_eq!(Outcome::<i32>::Loss(2).encode(), i32::MIN + 1 + 2);
+        // the faster wins and the slower losses are better
+        assert!(Outcome::<i32>::Win(1).encode() > Outcome::Win(3).encode());
+        assert!(Outcome::<i32>::Loss(4).encode() > Outcome::Loss(2).encode());
+
+        // the heuristic weights stay below the wins and above the losses
+        let highest = Outcome::Heuristic(i32::MAX).encode();
+        assert!(highest < Outcome::Win(MATE_RANGE as u32).encode());
+        assert_eq!(Outcome::decode(highest), Outcome::Heuristic(highest));
+        let lowest = Outcome::Heuristic(-i32::MAX).encode();
+        assert!(lowest > Outcome::Loss(MATE_RANGE as u32).encode());
+        assert_eq!(Outcome::decode(lowest), Outcome::Heuristic(lowest));
+
+        // the distances are relative to the position the outcome is seen from
+        assert_eq!(Outcome::<i32>::Win(5).shift(2), Outcome::Win(3));
+        assert_eq!(Outcome::<i32>::Loss(4).shift(-2), Outcome::Loss(6));
+        assert_eq!(Outcome::Heuristic(12).shift(3), Outcome::Heuristic(12));
+    }
+
+    #[test]
+    fn table_scores_are_relative_to_their_position() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let config = SearchConfig::default().with_depth_penalty(DepthPenalty::PerPly(2));
+        let table = TranspositionTable::<ConnectFour>::new(1);
+        let cancellation = CancellationToken::new();
+        let search = Search::new(p1, 8, &config, 0, &GameScore, &table, None, &cancellation);
+
+        // a score of 100 found 3 plays after the root is stored as found at the root
+        let score = search.table_score(94, 3);
+        assert_eq!(score, 100);
+        // then read 5 plays after another root, with the penalty of the new distance
+        assert_eq!(search.table_weight(score, 5), 90);
+        assert_eq!(search.table_weight(score, 3), 94);
+        // the wins and losses are counted from the position
+        let score = search.table_score(Outcome::Win(6).encode(), 3);
+        assert_eq!(search.table_weight(score, 1), Outcome::Win(4).encode());
+    }
+
+    #[test]
+    fn analysis_weights_are_exact() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        let positions: [&[usize]; 3] = [&[], &[4, 4, 3, 5], &[3, 3, 4, 4]];
+        for columns in positions {
+            let mut game = ConnectFour::new();
+            let mut player = p1;
+            for column in columns {
+                game.play(player, NonZeroUsize::new(*column).unwrap())
+                    .unwrap();
+                player = player.other();
+            }
+            let depth = columns.len() as u32;
+            let mut game_tree = GameNode::new_root(game.clone(), player, depth);
+            let (tree_analysis, stats) = game_tree.analyze(
+                player,
+                5,
+                &SearchConfig::default(),
+                &GameScore,
+                &TranspositionTable::default(),
+                None,
+                &CancellationToken::new(),
+            );
+            assert!(stats.completed);
+            let tree_analysis = tree_analysis.unwrap();
+            let (analysis, _) = make_unmake::analyze(
+                &game,
+                player,
+                5,
+                &SearchConfig::default(),
+                &GameScore,
+                &TranspositionTable::default(),
+                None,
+                &CancellationToken::new(),
+            );
+            let analysis = analysis.unwrap();
+
+            assert_eq!(analysis.len(), game.possible_plays().len());
+            assert!(analysis.windows(2).all(|w| w[0].weight >= w[1].weight));
+            assert_eq!(game_tree.weight(), Some(analysis[0].weight));
+            // the weight of each play does not depend on the order of the plays
+            for analyzed in &analysis {
+                let from_tree = tree_analysis
+                    .iter()
+                    .find(|tree_analyzed| tree_analyzed.play == analyzed.play)
+                    .unwrap();
+                assert_eq!(from_tree.weight, analyzed.weight, "plays {columns:?}");
+                for variation in [&analyzed.variation, &from_tree.variation] {
+                    assert_eq!(variation[0], (analyzed.play, analyzed.weight));
+                }
+            }
+
+            // the best play is the one of a normal search
+            let mut game_tree = GameNode::new_root(game.clone(), player, depth);
+            game_tree.explore_children(
+                player,
+                5,
+                &SearchConfig::default(),
+                &GameScore,
+                &TranspositionTable::default(),
+                None,
+                &CancellationToken::new(),
+            );
+            assert_eq!(game_tree.weight(), Some(analysis[0].weight));
+        }
+    }
+
+    /// Weights found by the search before it was written as a negamax, with positions given by the
+    /// columns played from the start, for explorations of 3, 4 and 6 plays. The wins and losses
+    /// were then weighed by their distance, see `Outcome`.
+    const SEARCH_CORPUS: [(&str, [i32; 3]); 31] = [
+        ("254364271", [97, -106, -114]),
+        ("6127652647673156", [57, -136, -64]),
+        ("27752474665", [207, 116, 0]),
+        ("3", [0, -26, -54]),
+        ("453614647711371", [0, -286, -2147483641]),
+        ("4537127637234765656", [2147483646, 2147483646, 2147483646]),
+        ("7372562711274274563", [177, 66, 154]),
+        ("323477", [127, 116, 114]),
+        ("673", [27, -26, -54]),
+        ("65352253634261131", [2147483646, 2147483646, 2147483646]),
+        ("43435572", [2147483646, 2147483646, 2147483646]),
+        ("", [7, 0, 0]),
+        ("71", [27, 0, 14]),
+        ("35", [87, 0, 14]),
+        ("7", [0, -26, -24]),
+        ("27", [87, 0, 64]),
+        ("372", [0, -116, -94]),
+        ("71776535777144", [37, -176, -344]),
+        ("46146717472454", [2147483646, 2147483646, 2147483646]),
+        ("42311412756721216", [227, 46, 44]),
+        ("5212717", [147, 36, -24]),
+        ("56511647", [187, 156, 234]),
+        ("4236127575272", [227, 116, 174]),
+        ("654524617", [187, 86, 94]),
+        ("4337167237634723632", [-337, -2147483643, -2147483643]),
+        ("267615644172771734415476641", [117, -66, 2147483642]),
+        ("4115467441553267724761224", [-77, -296, -194]),
+        ("467611165265256243724577545", [297, 106, -4]),
+        ("2144512117677", [-77, -2147483643, -2147483643]),
+        ("3274371374254715", [217, 76, -2147483641]),
+        ("57634512562", [2147483644, 2147483644, 2147483644]),
+    ];
+
+    #[test]
+    fn search_matches_corpus() {
+        check_search_corpus();
+    }
+
+    #[test]
+    fn parallel_search_matches_corpus() {
+        // more threads than the cores of most test machines, so that the nodes are split
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        thread_pool.install(check_search_corpus);
+    }
+
+    fn check_search_corpus() {
+        let p1 = NonZeroU8::new(1).unwrap();
+        for (columns, weights) in SEARCH_CORPUS {
+            let mut game = ConnectFour::new();
+            let mut player = p1;
+            for column in columns.chars() {
+                let column = column.to_digit(10).unwrap() as usize;
+                game.play(player, NonZeroUsize::new(column).unwrap())
+                    .unwrap();
+                player = player.other();
+            }
+            let depth = columns.len() as u32;
+            for algorithm in [Algorithm::AlphaBeta, Algorithm::PrincipalVariation] {
+                let config = SearchConfig::default().with_algorithm(algorithm);
+                for (max_depth, weight) in [3, 4, 6].into_iter().zip(weights) {
+                    // small tables, as allocating the default one for each search is slow
+                    let mut game_tree = GameNode::new_root(game.clone(), player, depth);
+                    game_tree.explore_children(
+                        player,
+                        max_depth,
+                        &config,
+                        &GameScore,
+                        &TranspositionTable::new(1 << 20),
+                        None,
+                        &CancellationToken::new(),
+                    );
+                    assert_eq!(
+                        game_tree.weight(),
+                        Some(weight),
+                        "plays {columns}, depth {max_depth}, {algorithm:?}"
+                    );
+                    let (variation, _) = make_unmake::best_play(
+                        &game,
+                        player,
+                        max_depth,
+                        &config,
+                        &GameScore,
+                        &TranspositionTable::new(1 << 20),
+                        None,
+                        &CancellationToken::new(),
+                    );
+                    assert_eq!(variation.unwrap()[0].1, weight);
+                }
+            }
+        }
+    }
+}
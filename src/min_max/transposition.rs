@@ -0,0 +1,125 @@
+use std::mem::size_of;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering::Relaxed;
+
+use parking_lot::Mutex;
+
+use crate::game::Game;
+use crate::scalar::Scalar;
+
+/// What the stored score tells about the real score of the position
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is the real score of the position
+    Exact,
+    /// The real score is at least the stored score (the search was cut off while maximizing)
+    Lower,
+    /// The real score is at most the stored score (the search was cut off while minimizing)
+    Upper,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TableEntry<S: Scalar, P> {
+    key: u64,
+    /// Number of plays that were explored below the position
+    pub depth: u32,
+    pub score: S,
+    pub bound: Bound,
+    /// The best play found for the position, explored first by the next searches. None if all the
+    /// plays were cut off.
+    pub best_play: Option<P>,
+    /// The search in which the entry was stored, used to replace old entries first
+    generation: u32,
+}
+
+/// The entry of a position in the table of a game
+pub type Entry<G> = TableEntry<<G as Game>::Score, <G as Game>::InputCoordinate>;
+
+/// A cache of already explored positions, shared between all the threads of a search.
+///
+/// The table has a fixed number of slots, computed from the memory limit given at creation, so
+/// that it never grows during long games. When two positions fall in the same slot, the entry
+/// from an older search or with the shallowest depth is replaced.
+pub struct TranspositionTable<G: Game> {
+    slots: Vec<Mutex<Option<Entry<G>>>>,
+    generation: AtomicU32,
+}
+
+impl<G: Game> TranspositionTable<G> {
+    pub const DEFAULT_MEMORY: usize = 64 * 1024 * 1024;
+
+    /// Creates a table using at most `max_memory` bytes (and at least one slot)
+    pub fn new(max_memory: usize) -> Self {
+        let slot_size = size_of::<Mutex<Option<Entry<G>>>>();
+        let mut slot_count = (max_memory / slot_size).max(1);
+        // a power of two allows finding the slot of a key with a mask
+        if !slot_count.is_power_of_two() {
+            slot_count = slot_count.next_power_of_two() >> 1;
+        }
+        Self {
+            slots: (0..slot_count).map(|_| Mutex::new(None)).collect(),
+            generation: AtomicU32::new(0),
+        }
+    }
+
+    /// Should be called before each new search so that entries from previous searches are
+    /// replaced first
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Relaxed);
+    }
+
+    /// Whether `entry` was stored during the current search, see `new_search`
+    pub fn is_current(&self, entry: &Entry<G>) -> bool {
+        entry.generation == self.generation.load(Relaxed)
+    }
+
+    pub fn get(&self, key: u64) -> Option<Entry<G>> {
+        let slot = self.slot(key).lock();
+        slot.filter(|entry| entry.key == key)
+    }
+
+    pub fn store(
+        &self,
+        key: u64,
+        depth: u32,
+        score: G::Score,
+        bound: Bound,
+        best_play: Option<G::InputCoordinate>,
+    ) {
+        let generation = self.generation.load(Relaxed);
+        let mut slot = self.slot(key).lock();
+        // a play that was best before is still a good guess
+        let best_play = best_play.or(slot
+            .filter(|old| old.key == key)
+            .and_then(|old| old.best_play));
+        let replace = match *slot {
+            None => true,
+            Some(old) => {
+                old.key == key
+                    || old.generation != generation
+                    || depth > old.depth
+                    || (depth == old.depth && bound == Bound::Exact)
+            }
+        };
+        if replace {
+            *slot = Some(TableEntry {
+                key,
+                depth,
+                score,
+                bound,
+                best_play,
+                generation,
+            });
+        }
+    }
+
+    fn slot(&self, key: u64) -> &Mutex<Option<Entry<G>>> {
+        &self.slots[key as usize & (self.slots.len() - 1)]
+    }
+}
+
+impl<G: Game> Default for TranspositionTable<G> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MEMORY)
+    }
+}
@@ -1,10 +1,24 @@
 use std::fmt::{Debug, Display};
-use std::ops::{Add, Sub};
+use std::ops::{Add, Neg, Sub};
 
 #[allow(non_snake_case)]
 pub trait Scalar:
-    Sized + Add + Sub + PartialEq + Ord + Copy + Clone + Display + Debug + Send + Sync + From<i32>
+    Sized
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Neg<Output = Self>
+    + PartialEq
+    + Ord
+    + Copy
+    + Clone
+    + Display
+    + Debug
+    + Send
+    + Sync
+    + From<i32>
+    + TryInto<i32>
 {
+    /// Should be `-MAX()`, so that the scores can be negated without overflowing
     fn MIN() -> Self;
     fn MAX() -> Self;
 
@@ -20,7 +34,7 @@ pub trait Scalar:
 #[allow(non_snake_case)]
 impl Scalar for i32 {
     fn MIN() -> Self {
-        i32::MIN
+        -i32::MAX
     }
 
     fn MAX() -> Self {